@@ -1,7 +1,10 @@
-use std::{fs, hint::black_box};
+use std::fs;
+#[cfg(feature = "alloc")]
+use std::hint::black_box;
 
 use criterion::{Criterion, criterion_group, criterion_main};
 
+#[cfg(feature = "alloc")]
 use json_escape_simd::{escape, escape_generic};
 
 fn get_rxjs_sources() -> Vec<String> {
@@ -37,6 +40,7 @@ fn get_fixture_sources() -> Vec<String> {
     sources
 }
 
+#[cfg(feature = "alloc")]
 fn run_benchmarks(c: &mut Criterion, sources: &[String], prefix: &str) {
     c.bench_function(&format!("{} escape simd", prefix), |b| {
         b.iter(|| {
@@ -78,6 +82,10 @@ fn run_benchmarks(c: &mut Criterion, sources: &[String], prefix: &str) {
     });
 }
 
+// `escape` needs `alloc`, the slice-only build has nothing to compare
+#[cfg(not(feature = "alloc"))]
+fn run_benchmarks(_c: &mut Criterion, _sources: &[String], _prefix: &str) {}
+
 fn rxjs_benchmark(c: &mut Criterion) {
     let sources = get_rxjs_sources();
     if !sources.is_empty() {
@@ -92,5 +100,66 @@ fn fixtures_benchmark(c: &mut Criterion) {
     }
 }
 
-criterion_group!(benches, rxjs_benchmark, fixtures_benchmark);
+#[cfg(feature = "rayon")]
+fn par_benchmark(c: &mut Criterion) {
+    let sources = get_fixture_sources();
+    if sources.is_empty() {
+        return;
+    }
+    // one multi-megabyte string, like a bundled source map
+    let joined = sources.concat();
+    c.bench_function("joined fixtures escape simd", |b| {
+        b.iter(|| black_box(escape(&joined)))
+    });
+    c.bench_function("joined fixtures par_escape", |b| {
+        b.iter(|| black_box(json_escape_simd::par_escape(&joined)))
+    });
+}
+
+#[cfg(not(feature = "rayon"))]
+fn par_benchmark(_c: &mut Criterion) {}
+
+#[cfg(all(feature = "bytes", feature = "alloc"))]
+fn buf_benchmark(c: &mut Criterion) {
+    use bytes::{BufMut, BytesMut};
+
+    let sources = get_fixture_sources();
+    if sources.is_empty() {
+        return;
+    }
+    let mut buf = BytesMut::new();
+    c.bench_function("fixtures escape_into_buf", |b| {
+        b.iter(|| {
+            buf.clear();
+            for source in &sources {
+                json_escape_simd::escape_into_buf(source, &mut buf);
+            }
+            black_box(&buf);
+        })
+    });
+    // the alternative without `escape_into_buf`: escape into a scratch `Vec` and copy it over
+    let mut scratch = Vec::new();
+    c.bench_function("fixtures escape_into + put_slice", |b| {
+        b.iter(|| {
+            buf.clear();
+            for source in &sources {
+                scratch.clear();
+                json_escape_simd::escape_into(source, &mut scratch);
+                buf.put_slice(&scratch);
+            }
+            black_box(&buf);
+        })
+    });
+}
+
+#[cfg(not(all(feature = "bytes", feature = "alloc")))]
+fn buf_benchmark(_c: &mut Criterion) {}
+
+criterion_group!(
+    benches,
+    rxjs_benchmark,
+    fixtures_benchmark,
+    par_benchmark,
+    buf_benchmark
+);
 criterion_main!(benches);
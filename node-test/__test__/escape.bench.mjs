@@ -0,0 +1,54 @@
+import { copyFileSync } from 'node:fs'
+import { createRequire } from 'node:module'
+import { join } from 'node:path'
+
+const require = createRequire(import.meta.url)
+const target = join(import.meta.dirname, '..', '..', 'target', process.env.NODE_TEST_PROFILE ?? 'release')
+const addon = join(target, 'node_test.node')
+copyFileSync(join(target, process.platform === 'win32' ? 'node_test.dll' : process.platform === 'darwin' ? 'libnode_test.dylib' : 'libnode_test.so'), addon)
+const { escape, escapeToBuffer, escapeUtf8, escapeUtf8ToBuffer } = require(addon)
+
+const fixtures = {
+  'ascii key': 'user_id',
+  'ascii 1 KiB': 'hello "world" \\ \n'.repeat(64),
+  'ascii 64 KiB': 'lorem ipsum dolor sit amet, "consectetur" adipiscing elit\n'.repeat(1130),
+  'cjk 1 KiB': '中文 "quoted" \\ \t🦀'.repeat(48),
+  'cjk 64 KiB': '中文 "quoted" \\ \t🦀'.repeat(3000),
+}
+
+function bench(fn, input) {
+  // run for roughly 200ms and report the time per call
+  let iterations = 0
+  const start = process.hrtime.bigint()
+  let elapsed = 0n
+  while (elapsed < 200_000_000n) {
+    for (let i = 0; i < 100; i++) fn(input)
+    iterations += 100
+    elapsed = process.hrtime.bigint() - start
+  }
+  return Number(elapsed) / iterations
+}
+
+const paths = [
+  ['string', escape, escapeUtf8],
+  ['Buffer', escapeToBuffer, escapeUtf8ToBuffer],
+]
+
+const rows = []
+for (const [name, input] of Object.entries(fixtures)) {
+  for (const [output, native, utf8] of paths) {
+    // warm up both paths
+    bench(native, input)
+    bench(utf8, input)
+    const nativeNs = bench(native, input)
+    const utf8Ns = bench(utf8, input)
+    rows.push({
+      fixture: name,
+      output,
+      'escape (ns)': nativeNs.toFixed(0),
+      'utf8 copy (ns)': utf8Ns.toFixed(0),
+      speedup: `${(utf8Ns / nativeNs).toFixed(2)}x`,
+    })
+  }
+}
+console.table(rows)
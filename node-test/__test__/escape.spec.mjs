@@ -0,0 +1,29 @@
+import assert from 'node:assert/strict'
+import { copyFileSync } from 'node:fs'
+import { createRequire } from 'node:module'
+import { join } from 'node:path'
+import { test } from 'node:test'
+
+const require = createRequire(import.meta.url)
+const target = join(import.meta.dirname, '..', '..', 'target', process.env.NODE_TEST_PROFILE ?? 'debug')
+const addon = join(target, 'node_test.node')
+copyFileSync(join(target, process.platform === 'win32' ? 'node_test.dll' : process.platform === 'darwin' ? 'libnode_test.dylib' : 'libnode_test.so'), addon)
+const { escape, escapeToBuffer } = require(addon)
+
+const fixtures = [
+  '',
+  'hello world',
+  'a'.repeat(1000),
+  '中文 "quoted" \\ \t\u0001\u001f 🦀'.repeat(50),
+  Array.from({ length: 128 }, (_, i) => String.fromCharCode(i)).join(''),
+  'lone \ud800 surrogate \udfff',
+  'trailing nul\0',
+]
+
+for (const [i, fixture] of fixtures.entries()) {
+  test(`matches JSON.stringify #${i}`, () => {
+    const expected = JSON.stringify(fixture)
+    assert.equal(escape(fixture), expected)
+    assert.deepEqual(escapeToBuffer(fixture), Buffer.from(expected))
+  })
+}
@@ -0,0 +1,29 @@
+use napi::{Env, JsBuffer, JsString, Result};
+use napi_derive::napi;
+
+#[napi]
+pub fn escape(env: Env, input: JsString) -> Result<JsString> {
+    json_escape_simd::escape_js_string(&env, input)
+}
+
+#[napi]
+pub fn escape_to_buffer(env: Env, input: JsString) -> Result<JsBuffer> {
+    json_escape_simd::escape_js_string_to_buffer(&env, input)
+}
+
+/// The `napi_get_value_string_utf8` paths `escape` / `escape_to_buffer` replace, kept as the
+/// benchmark baseline.
+#[napi]
+pub fn escape_utf8(env: Env, input: JsString) -> Result<JsString> {
+    let input = input.into_utf8()?;
+    env.create_string_from_std(json_escape_simd::escape(input.as_str()?))
+}
+
+#[napi]
+pub fn escape_utf8_to_buffer(env: Env, input: JsString) -> Result<JsBuffer> {
+    let input = input.into_utf8()?;
+    let escaped = json_escape_simd::escape(input.as_str()?);
+    Ok(env
+        .create_buffer_with_data(escaped.into_bytes())?
+        .into_raw())
+}
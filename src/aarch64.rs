@@ -1,13 +1,21 @@
-use std::arch::aarch64::{
-    vceqq_u8, vdupq_n_u8, vld1q_u8_x4, vmaxvq_u8, vorrq_u8, vqtbl4q_u8, vst1q_u8,
+use core::arch::aarch64::{
+    uint8x16_t, vaddq_u8, vandq_u8, vbslq_u8, vceqq_u8, vcltq_u8, vdupq_n_u8, vget_high_u8,
+    vget_lane_u64, vget_low_u8, vld1q_u8, vld1q_u8_x4, vmaxvq_u8, vorrq_u8, vqtbl1q_u8, vqtbl4q_u8,
+    vreinterpret_u64_u8, vreinterpretq_u8_u32, vreinterpretq_u16_u8, vreinterpretq_u32_u16,
+    vshrn_n_u16, vshrq_n_u8, vst1_u8, vst1q_u8, vsubq_u8, vtstq_u8, vzip1q_u8, vzip1q_u16,
+    vzip1q_u32, vzip2q_u8, vzip2q_u16, vzip2q_u32,
 };
 
-use crate::generic::{ESCAPE, HEX_BYTES, UU};
+use alloc::vec::Vec;
+
+use crate::generic::{ByteClass, ESCAPE, ESCAPE_SEQ_SIZE, UU, next_in_class, write_escape};
 
 const CHUNK: usize = 64;
+const VECTOR_SIZE: usize = 16;
 // 128 bytes ahead
 const PREFETCH_DISTANCE: usize = CHUNK * 2;
 const SLASH_SENTINEL: u8 = 0xFF;
+const HEX_DIGITS: &[u8; VECTOR_SIZE] = b"0123456789abcdef";
 
 #[inline]
 pub fn escape_neon(bytes: &[u8], output: &mut Vec<u8>) {
@@ -18,10 +26,6 @@ pub fn escape_neon(bytes: &[u8], output: &mut Vec<u8>) {
         let slash = vdupq_n_u8(b'\\');
         let mut i = 0usize;
 
-        // Scratch buffer reused for mask materialisation; stay uninitialised.
-        #[allow(invalid_value)]
-        let mut placeholder: [u8; 16] = core::mem::MaybeUninit::uninit().assume_init();
-
         while i + CHUNK <= n {
             let ptr = bytes.as_ptr().add(i);
 
@@ -37,6 +41,7 @@ pub fn escape_neon(bytes: &[u8], output: &mut Vec<u8>) {
             let c = quad.2;
             let d = quad.3;
 
+            // The table only covers 0..64, `\` is flagged with all bits set instead
             let mask_1 = vorrq_u8(vqtbl4q_u8(tbl, a), vceqq_u8(slash, a));
             let mask_2 = vorrq_u8(vqtbl4q_u8(tbl, b), vceqq_u8(slash, b));
             let mask_3 = vorrq_u8(vqtbl4q_u8(tbl, c), vceqq_u8(slash, c));
@@ -48,26 +53,31 @@ pub fn escape_neon(bytes: &[u8], output: &mut Vec<u8>) {
             let mask_r_4 = vmaxvq_u8(mask_4);
 
             if mask_r_1 | mask_r_2 | mask_r_3 | mask_r_4 == 0 {
-                output.extend_from_slice(std::slice::from_raw_parts(ptr, CHUNK));
+                output.extend_from_slice(core::slice::from_raw_parts(ptr, CHUNK));
                 i += CHUNK;
                 continue;
             }
 
             macro_rules! handle {
-                ($mask:expr, $mask_r:expr, $off:expr) => {
+                ($src:expr, $mask:expr, $mask_r:expr, $off:expr) => {
                     if $mask_r == 0 {
-                        output.extend_from_slice(std::slice::from_raw_parts(ptr.add($off), 16));
+                        output.extend_from_slice(core::slice::from_raw_parts(
+                            ptr.add($off),
+                            VECTOR_SIZE,
+                        ));
                     } else {
-                        vst1q_u8(placeholder.as_mut_ptr(), $mask);
-                        handle_block(&bytes[i + $off..i + $off + 16], &placeholder, output);
+                        output.reserve(VECTOR_SIZE * ESCAPE_SEQ_SIZE);
+                        let len = output.len();
+                        let written = escape_block_neon($src, $mask, output.as_mut_ptr().add(len));
+                        output.set_len(len + written);
                     }
                 };
             }
 
-            handle!(mask_1, mask_r_1, 0);
-            handle!(mask_2, mask_r_2, 16);
-            handle!(mask_3, mask_r_3, 32);
-            handle!(mask_4, mask_r_4, 48);
+            handle!(a, mask_1, mask_r_1, 0);
+            handle!(b, mask_2, mask_r_2, VECTOR_SIZE);
+            handle!(c, mask_3, mask_r_3, VECTOR_SIZE * 2);
+            handle!(d, mask_4, mask_r_4, VECTOR_SIZE * 3);
 
             i += CHUNK;
         }
@@ -78,6 +88,35 @@ pub fn escape_neon(bytes: &[u8], output: &mut Vec<u8>) {
     }
 }
 
+/// Index of the first byte at or after `i` that is in `class`, or `bytes.len()`.
+#[inline]
+pub fn find_in_class_neon<const N: usize>(
+    bytes: &[u8],
+    mut i: usize,
+    class: ByteClass<N>,
+) -> usize {
+    unsafe {
+        let below = vdupq_n_u8(class.below);
+        let v_bytes = class.bytes.map(|b| vdupq_n_u8(b));
+
+        while i + VECTOR_SIZE <= bytes.len() {
+            let a = vld1q_u8(bytes.as_ptr().add(i));
+            let mut mask = vcltq_u8(a, below);
+            for v in v_bytes {
+                mask = vorrq_u8(mask, vceqq_u8(a, v));
+            }
+            if vmaxvq_u8(mask) != 0 {
+                // narrow every byte of the mask to a nibble, the first set nibble is the match
+                let nibbles = vshrn_n_u16::<4>(vreinterpretq_u16_u8(mask));
+                let bits = vget_lane_u64::<0>(vreinterpret_u64_u8(nibbles));
+                return i + (bits.trailing_zeros() / 4) as usize;
+            }
+            i += VECTOR_SIZE;
+        }
+    }
+    next_in_class(bytes, i, class)
+}
+
 #[inline(always)]
 fn handle_tail(src: &[u8], dst: &mut Vec<u8>) {
     for &c in src {
@@ -85,35 +124,77 @@ fn handle_tail(src: &[u8], dst: &mut Vec<u8>) {
         if escape_byte == 0 {
             dst.push(c);
         } else {
-            write_escape(dst, escape_byte, c);
+            write_escape(dst, c);
         }
     }
 }
 
+/// Escape the 16 bytes in `a` into `dst` without a per-byte branch.
+///
+/// `mask` holds the escape letter of every byte, or [`SLASH_SENTINEL`] for `\`. Each byte is
+/// expanded into an 8-byte slot (`\`, letter, `00`, hex digits) by interleaving, the slots are
+/// stored at full width and the cursor advances by the real sequence length. `dst` must have room
+/// for `16 * 8` bytes. Returns the number of bytes written.
 #[inline(always)]
-fn handle_block(src: &[u8], mask: &[u8; 16], dst: &mut Vec<u8>) {
-    for (j, &m) in mask.iter().enumerate() {
-        let c = src[j];
-        if m == 0 {
-            dst.push(c);
-        } else if m == SLASH_SENTINEL {
-            dst.push(b'\\');
-            dst.push(b'\\');
-        } else {
-            write_escape(dst, m, c);
+unsafe fn escape_block_neon(a: uint8x16_t, mask: uint8x16_t, dst: *mut u8) -> usize {
+    unsafe {
+        let escaped = vtstq_u8(mask, mask);
+        let letters = vbslq_u8(
+            vceqq_u8(mask, vdupq_n_u8(SLASH_SENTINEL)),
+            vdupq_n_u8(b'\\'),
+            mask,
+        );
+        let unicode = vceqq_u8(mask, vdupq_n_u8(UU));
+
+        let first = vbslq_u8(escaped, vdupq_n_u8(b'\\'), a);
+        let zeros = vdupq_n_u8(b'0');
+        let hex_hi = vorrq_u8(zeros, vandq_u8(vshrq_n_u8::<4>(a), vdupq_n_u8(0x01)));
+        let hex_lo = vqtbl1q_u8(vld1q_u8(HEX_DIGITS.as_ptr()), vandq_u8(a, vdupq_n_u8(0x0F)));
+
+        // 1 for plain bytes, 2 for short escapes, 6 for `\u00XX`
+        let lens = vaddq_u8(
+            vsubq_u8(vdupq_n_u8(1), escaped),
+            vandq_u8(unicode, vdupq_n_u8(4)),
+        );
+        let mut lengths = [0u8; VECTOR_SIZE];
+        vst1q_u8(lengths.as_mut_ptr(), lens);
+
+        // Interleave into 8-byte slots: [first, letter, '0', '0', hex_hi, hex_lo, 0, 0]
+        let zeros = vreinterpretq_u16_u8(zeros);
+        let zero = vreinterpretq_u16_u8(vdupq_n_u8(0));
+        let pairs_lo = vreinterpretq_u16_u8(vzip1q_u8(first, letters));
+        let pairs_hi = vreinterpretq_u16_u8(vzip2q_u8(first, letters));
+        let hex_lo_pairs = vreinterpretq_u16_u8(vzip1q_u8(hex_hi, hex_lo));
+        let hex_hi_pairs = vreinterpretq_u16_u8(vzip2q_u8(hex_hi, hex_lo));
+        let heads = [
+            vzip1q_u16(pairs_lo, zeros),
+            vzip2q_u16(pairs_lo, zeros),
+            vzip1q_u16(pairs_hi, zeros),
+            vzip2q_u16(pairs_hi, zeros),
+        ];
+        let tails = [
+            vzip1q_u16(hex_lo_pairs, zero),
+            vzip2q_u16(hex_lo_pairs, zero),
+            vzip1q_u16(hex_hi_pairs, zero),
+            vzip2q_u16(hex_hi_pairs, zero),
+        ];
+
+        let mut out = dst;
+        for q in 0..4 {
+            let head = vreinterpretq_u32_u16(heads[q]);
+            let tail = vreinterpretq_u32_u16(tails[q]);
+            let slots = [
+                vreinterpretq_u8_u32(vzip1q_u32(head, tail)),
+                vreinterpretq_u8_u32(vzip2q_u32(head, tail)),
+            ];
+            for (s, slot) in slots.into_iter().enumerate() {
+                let j = q * 4 + s * 2;
+                vst1_u8(out, vget_low_u8(slot));
+                out = out.add(lengths[j] as usize);
+                vst1_u8(out, vget_high_u8(slot));
+                out = out.add(lengths[j + 1] as usize);
+            }
         }
-    }
-}
-
-#[inline(always)]
-fn write_escape(dst: &mut Vec<u8>, escape_byte: u8, c: u8) {
-    dst.push(b'\\');
-    if escape_byte == UU {
-        dst.extend_from_slice(b"u00");
-        let hex = &HEX_BYTES[c as usize];
-        dst.push(hex.0);
-        dst.push(hex.1);
-    } else {
-        dst.push(escape_byte);
+        out.offset_from(dst) as usize
     }
 }
@@ -0,0 +1,95 @@
+use core::{future::poll_fn, pin::Pin};
+use std::io;
+
+use crate::{Vec, escape_bytes};
+
+/// Input bytes escaped per chunk, the chunk buffer never grows past the worst case of
+/// `ASYNC_CHUNK_SIZE * 6 + 2` bytes.
+const ASYNC_CHUNK_SIZE: usize = 16 * 1024;
+
+/// Escapes the input one bounded chunk at a time into a reused buffer.
+///
+/// Escaping is byte-wise, so chunk boundaries may split a UTF-8 sequence and the concatenated
+/// chunks are still identical to [`escape`](crate::escape).
+struct EscapedChunks<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    done: bool,
+    buf: Vec<u8>,
+}
+
+impl<'a> EscapedChunks<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        let len = bytes.len().min(ASYNC_CHUNK_SIZE);
+        EscapedChunks {
+            bytes,
+            pos: 0,
+            done: false,
+            buf: Vec::with_capacity(len + len / 2 + 2),
+        }
+    }
+
+    fn next_chunk(&mut self) -> Option<&[u8]> {
+        if self.done {
+            return None;
+        }
+        self.buf.clear();
+        if self.pos == 0 {
+            self.buf.push(b'"');
+        }
+        let end = self.bytes.len().min(self.pos + ASYNC_CHUNK_SIZE);
+        escape_bytes(&self.bytes[self.pos..end], &mut self.buf);
+        self.pos = end;
+        if end == self.bytes.len() {
+            self.buf.push(b'"');
+            self.done = true;
+        }
+        Some(&self.buf)
+    }
+}
+
+/// Escape `input`, surrounded with quotes, into a [`tokio::io::AsyncWrite`].
+///
+/// The input is escaped through the SIMD kernels in chunks of 16 KiB and every chunk is written
+/// before the next one is escaped, so memory use stays flat regardless of the input size. The
+/// writer is not flushed.
+#[cfg(feature = "tokio")]
+pub async fn escape_to_async_writer<S, W>(input: S, writer: &mut W) -> io::Result<()>
+where
+    S: AsRef<str>,
+    W: tokio::io::AsyncWrite + Unpin + ?Sized,
+{
+    let mut chunks = EscapedChunks::new(input.as_ref().as_bytes());
+    while let Some(mut chunk) = chunks.next_chunk() {
+        while !chunk.is_empty() {
+            let n = poll_fn(|cx| Pin::new(&mut *writer).poll_write(cx, chunk)).await?;
+            if n == 0 {
+                return Err(io::ErrorKind::WriteZero.into());
+            }
+            chunk = &chunk[n..];
+        }
+    }
+    Ok(())
+}
+
+/// Escape `input`, surrounded with quotes, into a [`futures_io::AsyncWrite`].
+///
+/// Same as `escape_to_async_writer`, for the `futures` family of runtimes.
+#[cfg(feature = "futures-io")]
+pub async fn escape_to_futures_writer<S, W>(input: S, writer: &mut W) -> io::Result<()>
+where
+    S: AsRef<str>,
+    W: futures_io::AsyncWrite + Unpin + ?Sized,
+{
+    let mut chunks = EscapedChunks::new(input.as_ref().as_bytes());
+    while let Some(mut chunk) = chunks.next_chunk() {
+        while !chunk.is_empty() {
+            let n = poll_fn(|cx| Pin::new(&mut *writer).poll_write(cx, chunk)).await?;
+            if n == 0 {
+                return Err(io::ErrorKind::WriteZero.into());
+            }
+            chunk = &chunk[n..];
+        }
+    }
+    Ok(())
+}
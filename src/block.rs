@@ -0,0 +1,85 @@
+use alloc::vec::Vec;
+
+use crate::generic::{ESCAPE, ESCAPE_SEQ, ESCAPE_SEQ_SIZE, MAX_ESCAPE_LEN, write_escape_ptr};
+
+/// Reserve room for a `block`-byte block plus the `pending` unescaped bytes before it, and return
+/// the write position.
+///
+/// The block is then written through the returned pointer and committed once with
+/// [`commit_block`], instead of checking the capacity on every append. When the worst case of
+/// every byte becoming `\u00XX` doesn't fit, `extra` counts what the block's escapes really add,
+/// so the up-front capacity of [`escape`](crate::escape) holds for lightly escaped input.
+#[inline(always)]
+pub(crate) unsafe fn reserve_block(
+    result: &mut Vec<u8>,
+    pending: usize,
+    block: usize,
+    extra: impl FnOnce() -> usize,
+) -> *mut u8 {
+    // escapes are written with full-width stores that may run past the real sequence
+    if result.capacity() - result.len() < pending + block * MAX_ESCAPE_LEN + ESCAPE_SEQ_SIZE {
+        result.reserve(pending + block + extra() + ESCAPE_SEQ_SIZE);
+    }
+    unsafe { result.as_mut_ptr().add(result.len()) }
+}
+
+/// Bytes the escapes in `mask` add on top of the input, bit `i` of `mask` is the byte at `ptr + i`.
+#[inline(always)]
+pub(crate) unsafe fn escaped_extra(ptr: *const u8, mask: u64) -> usize {
+    let mut extra = 0;
+    let mut remaining = mask;
+    while remaining != 0 {
+        let c = unsafe { *ptr.add(remaining.trailing_zeros() as usize) };
+        extra += ESCAPE_SEQ[c as usize][ESCAPE_SEQ_SIZE - 1] as usize - 1;
+        remaining &= remaining - 1;
+    }
+    extra
+}
+
+#[inline(always)]
+pub(crate) unsafe fn commit_block(result: &mut Vec<u8>, dst: *mut u8) {
+    let len = dst as usize - result.as_ptr() as usize;
+    debug_assert!(len <= result.capacity());
+    unsafe { result.set_len(len) };
+}
+
+#[inline(always)]
+pub(crate) unsafe fn copy_pending(dst: *mut u8, bytes: &[u8], start: usize, end: usize) -> *mut u8 {
+    let n = end - start;
+    unsafe {
+        core::ptr::copy_nonoverlapping(bytes.as_ptr().add(start), dst, n);
+        dst.add(n)
+    }
+}
+
+/// Escape the bytes flagged in `mask` into `dst`, bit `i` of `mask` is the byte at `ptr + i` and
+/// `at` is its offset in `bytes`. Unescaped bytes from `start` up to each escape are copied first.
+#[inline(always)]
+pub(crate) unsafe fn process_mask_bits(
+    ptr: *const u8,
+    at: usize,
+    mut dst: *mut u8,
+    start: &mut usize,
+    bytes: &[u8],
+    mask: u64,
+) -> *mut u8 {
+    let mut remaining = mask;
+    while remaining != 0 {
+        let cur = remaining.trailing_zeros() as usize;
+        let c = unsafe { *ptr.add(cur) };
+        debug_assert!(ESCAPE[c as usize] != 0);
+
+        let i = at + cur;
+        unsafe {
+            if *start < i {
+                dst = copy_pending(dst, bytes, *start, i);
+            }
+            dst = write_escape_ptr(dst, c);
+        }
+        *start = i + 1;
+
+        // Clear the lowest set bit
+        remaining &= remaining - 1;
+    }
+    dst
+}
@@ -0,0 +1,68 @@
+use bytes::BufMut;
+
+#[cfg(not(feature = "alloc"))]
+use crate::generic::next_escape;
+use crate::generic::{ESCAPE_SEQ, ESCAPE_SEQ_SIZE};
+#[cfg(feature = "alloc")]
+use crate::{Backend, generic::ByteClass};
+
+/// Escape `input`, surrounded with quotes, directly into a [`BufMut`] such as `BytesMut`.
+///
+/// Clean runs are written with [`BufMut::put_slice`], which bulk copies into
+/// [`chunk_mut`](BufMut::chunk_mut) and moves on to the next chunk when one is full, so
+/// non-contiguous buffers are handled. Escape sequences are stored straight into `chunk_mut` when
+/// it has room, and may straddle chunks otherwise. With `alloc` the scan uses the vector compares
+/// of the kernels for the current CPU, without it the word-at-a-time scanner of the generic
+/// kernel. No intermediate `Vec` is involved.
+///
+/// Escapes are still handled one at a time, so input dense with escapes is faster through
+/// `escape_into` into a reused `Vec` and a single `put_slice`, see the `escape_into_buf`
+/// benchmarks.
+///
+/// # Panics
+///
+/// Panics if `buf` runs out of space, like [`BufMut::put_slice`].
+pub fn escape_into_buf<S: AsRef<str>, B: BufMut>(input: S, buf: &mut B) {
+    let bytes = input.as_ref().as_bytes();
+    buf.put_u8(b'"');
+    escape_buf_inner(bytes, buf);
+    buf.put_u8(b'"');
+}
+
+#[inline]
+fn escape_buf_inner<B: BufMut>(bytes: &[u8], buf: &mut B) {
+    #[cfg(feature = "alloc")]
+    let backend = Backend::detect();
+    let len = bytes.len();
+    let mut start = 0;
+
+    loop {
+        #[cfg(feature = "alloc")]
+        let i = backend.find_in_class(bytes, start, ByteClass::JSON);
+        #[cfg(not(feature = "alloc"))]
+        let i = next_escape(bytes, start);
+
+        // Copy any unescaped bytes before this position
+        if start < i {
+            buf.put_slice(&bytes[start..i]);
+        }
+
+        if i >= len {
+            break;
+        }
+
+        let seq = &ESCAPE_SEQ[bytes[i] as usize];
+        let seq_len = seq[ESCAPE_SEQ_SIZE - 1] as usize;
+        let chunk = buf.chunk_mut();
+        if chunk.len() >= ESCAPE_SEQ_SIZE {
+            // SAFETY: the chunk has room for the whole table entry, only the sequence is committed
+            unsafe {
+                core::ptr::write_unaligned(chunk.as_mut_ptr() as *mut [u8; ESCAPE_SEQ_SIZE], *seq);
+                buf.advance_mut(seq_len);
+            }
+        } else {
+            buf.put_slice(&seq[..seq_len]);
+        }
+        start = i + 1;
+    }
+}
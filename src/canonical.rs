@@ -0,0 +1,57 @@
+use crate::generic::{ByteClass, escape_class};
+use crate::{String, Vec};
+
+// Kept apart from the kernels' `ByteClass::JSON` and escape tables, so changes to `escape` can't
+// alter the canonical output
+const CANONICAL: ByteClass<2> = ByteClass {
+    bytes: *b"\"\\",
+    below: 0x20,
+};
+
+const HEX: &[u8; 16] = b"0123456789abcdef";
+
+/// Escape `input` as a quoted string in canonical JSON form (RFC 8785, JCS).
+///
+/// The output uses exactly the short escapes `\b \t \n \f \r \" \\`, lowercase `\u00XX` for the
+/// other control characters, and copies every other character as UTF-8. Unlike [`escape`], this
+/// form is part of the API contract and won't change across versions, so it is safe to sign.
+///
+/// [`escape`]: crate::escape
+pub fn escape_canonical<S: AsRef<str>>(input: S) -> String {
+    let input = input.as_ref();
+    let mut output = Vec::with_capacity(input.len() + input.len() / 2 + 2);
+    escape_canonical_into(input, &mut output);
+    // SAFETY: only ASCII escape sequences were added, the rest is copied from a `str`
+    unsafe { String::from_utf8_unchecked(output) }
+}
+
+/// Same as [`escape_canonical`], appending to `output`.
+pub fn escape_canonical_into<S: AsRef<str>>(input: S, output: &mut Vec<u8>) {
+    output.push(b'"');
+    escape_class(
+        input.as_ref().as_bytes(),
+        output,
+        CANONICAL,
+        |output, rest| {
+            match rest[0] {
+                b'\x08' => output.extend_from_slice(b"\\b"),
+                b'\t' => output.extend_from_slice(b"\\t"),
+                b'\n' => output.extend_from_slice(b"\\n"),
+                b'\x0c' => output.extend_from_slice(b"\\f"),
+                b'\r' => output.extend_from_slice(b"\\r"),
+                b'"' => output.extend_from_slice(b"\\\""),
+                b'\\' => output.extend_from_slice(b"\\\\"),
+                b => output.extend_from_slice(&[
+                    b'\\',
+                    b'u',
+                    b'0',
+                    b'0',
+                    HEX[(b >> 4) as usize],
+                    HEX[(b & 0xf) as usize],
+                ]),
+            }
+            1
+        },
+    );
+    output.push(b'"');
+}
@@ -0,0 +1,60 @@
+use core::sync::atomic::{AtomicU8, Ordering};
+
+#[cfg(feature = "std")]
+const ENV_MAX_WIDTH: &str = "JSON_ESCAPE_SIMD_MAX_WIDTH";
+const UNSET: u8 = 0;
+
+static MAX_WIDTH: AtomicU8 = AtomicU8::new(UNSET);
+
+/// Widest vector registers the `x86_64` kernel selection is allowed to use.
+///
+/// On some Skylake-SP and Ice Lake servers, running 512-bit instructions lowers the core
+/// frequency for a while. When escaping is a short burst in an otherwise scalar workload, that
+/// can cost more than the wider kernel saves. With [`MaxVectorWidth::Bits256`], AVX-512VL hosts
+/// use a 256-bit kernel that keeps the mask registers, and other hosts fall back to AVX2.
+///
+/// With the `std` feature, the initial value is read from the `JSON_ESCAPE_SIMD_MAX_WIDTH`
+/// environment variable (`256` or `512`) on first use. It can be changed with
+/// [`set_max_vector_width`], and has no effect on other architectures.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum MaxVectorWidth {
+    /// Use 512-bit kernels when the CPU supports them.
+    #[default]
+    Bits512 = 1,
+    /// Never touch 512-bit registers.
+    Bits256 = 2,
+}
+
+/// Cap the vector width used by [`escape`](crate::escape) and [`escape_into`](crate::escape_into).
+///
+/// Takes precedence over the `JSON_ESCAPE_SIMD_MAX_WIDTH` environment variable.
+pub fn set_max_vector_width(width: MaxVectorWidth) {
+    MAX_WIDTH.store(width as u8, Ordering::Relaxed);
+}
+
+/// The vector width cap currently in effect, see [`MaxVectorWidth`].
+#[inline]
+pub fn max_vector_width() -> MaxVectorWidth {
+    match MAX_WIDTH.load(Ordering::Relaxed) {
+        UNSET => init_from_env(),
+        w if w == MaxVectorWidth::Bits256 as u8 => MaxVectorWidth::Bits256,
+        _ => MaxVectorWidth::Bits512,
+    }
+}
+
+#[cold]
+fn init_from_env() -> MaxVectorWidth {
+    #[cfg(feature = "std")]
+    let width = match std::env::var(ENV_MAX_WIDTH).as_deref() {
+        Ok("256") => MaxVectorWidth::Bits256,
+        _ => MaxVectorWidth::Bits512,
+    };
+    // no environment without `std`
+    #[cfg(not(feature = "std"))]
+    let width = MaxVectorWidth::default();
+    // a plain store, targets like `thumbv6m` have no compare-and-swap. A racing
+    // `set_max_vector_width` can at worst be overwritten by the default of the first call.
+    MAX_WIDTH.store(width as u8, Ordering::Relaxed);
+    width
+}
@@ -0,0 +1,73 @@
+use std::io;
+
+use simd_json::prelude::BaseGenerator;
+
+use crate::{Vec, escape_bytes};
+
+/// A `simd-json` [`BaseGenerator`] that writes compact JSON to `writer` and escapes strings with
+/// this crate's kernels instead of the generator's built-in escaper.
+///
+/// Useful for code that emits JSON through the `value_trait` generator API, so `simd-json` and
+/// [`escape`](crate::escape) share one escaping implementation.
+pub struct SimdJsonGenerator<W> {
+    writer: W,
+    scratch: Vec<u8>,
+}
+
+impl<W: io::Write> SimdJsonGenerator<W> {
+    /// Create a generator writing to `writer`.
+    #[inline]
+    pub fn new(writer: W) -> Self {
+        SimdJsonGenerator {
+            writer,
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Unwrap the writer.
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: io::Write> BaseGenerator for SimdJsonGenerator<W> {
+    type T = W;
+
+    #[inline]
+    fn get_writer(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    #[inline]
+    fn write_min(&mut self, _slice: &[u8], min: u8) -> io::Result<()> {
+        self.writer.write_all(&[min])
+    }
+
+    #[inline]
+    fn write_string(&mut self, string: &str) -> io::Result<()> {
+        self.scratch.clear();
+        self.scratch.push(b'"');
+        escape_bytes(string.as_bytes(), &mut self.scratch);
+        self.scratch.push(b'"');
+        self.writer.write_all(&self.scratch)
+    }
+
+    #[inline]
+    fn write_string_content(&mut self, string: &str) -> io::Result<()> {
+        self.scratch.clear();
+        escape_bytes(string.as_bytes(), &mut self.scratch);
+        self.writer.write_all(&self.scratch)
+    }
+
+    // the kernels already pick the short string paths, so "simple" strings need no special case
+    #[inline]
+    fn write_simple_string(&mut self, string: &str) -> io::Result<()> {
+        self.write_string(string)
+    }
+
+    #[inline]
+    fn write_simple_str_content(&mut self, string: &str) -> io::Result<()> {
+        self.write_string_content(string)
+    }
+}
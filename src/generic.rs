@@ -1,3 +1,10 @@
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
+
+#[cfg(feature = "alloc")]
+use crate::Backend;
+
+#[cfg(feature = "alloc")]
 #[inline]
 pub fn escape_generic<S: AsRef<str>>(s: S) -> String {
     let s = s.as_ref();
@@ -13,6 +20,7 @@ pub fn escape_generic<S: AsRef<str>>(s: S) -> String {
     unsafe { String::from_utf8_unchecked(result) }
 }
 
+#[cfg(feature = "alloc")]
 #[inline]
 pub fn escape_into_generic<S: AsRef<str>>(s: S, output: &mut Vec<u8>) {
     let s = s.as_ref();
@@ -22,53 +30,261 @@ pub fn escape_into_generic<S: AsRef<str>>(s: S, output: &mut Vec<u8>) {
     output.push(b'"');
 }
 
+const WORD_SIZE: usize = core::mem::size_of::<u64>();
+const LO_BITS: u64 = 0x0101_0101_0101_0101;
+const HI_BITS: u64 = 0x8080_8080_8080_8080;
+
+/// A set of bytes the scanners look for: `N` single bytes and everything below a limit.
+///
+/// JSON strings escape `"`, `\` and control characters, other formats swap in their own bytes,
+/// e.g. `~` and `/` in JSON Pointers. With `below: 0` no control characters are matched.
+#[derive(Clone, Copy)]
+pub(crate) struct ByteClass<const N: usize> {
+    pub(crate) bytes: [u8; N],
+    pub(crate) below: u8,
+}
+
+impl ByteClass<2> {
+    /// Bytes escaped in JSON strings.
+    pub(crate) const JSON: ByteClass<2> = ByteClass {
+        bytes: *b"\"\\",
+        below: 0x20,
+    };
+    /// Bytes escaped in JSON Pointer reference tokens (RFC 6901).
+    #[cfg(feature = "alloc")]
+    pub(crate) const JSON_POINTER: ByteClass<2> = ByteClass {
+        bytes: *b"~/",
+        below: 0,
+    };
+    /// Bytes escaped in single-quoted JSONPath name selectors (RFC 9535).
+    #[cfg(feature = "alloc")]
+    pub(crate) const JSONPATH: ByteClass<2> = ByteClass {
+        bytes: *b"'\\",
+        below: 0x20,
+    };
+}
+
+impl<const N: usize> ByteClass<N> {
+    #[cfg(feature = "alloc")]
+    #[inline(always)]
+    pub(crate) fn contains(self, b: u8) -> bool {
+        b < self.below || self.bytes.contains(&b)
+    }
+}
+
+/// SWAR classifier for 8 bytes at once.
+///
+/// Returns a word with the high bit of a byte set if that byte is in `class`. Borrows can only
+/// propagate upwards, so the lowest set bit is always exact, higher bits may be false positives
+/// and must be re-checked against the class.
+#[inline(always)]
+fn class_mask_swar<const N: usize>(word: u64, class: ByteClass<N>) -> u64 {
+    let mut mask = word.wrapping_sub(LO_BITS * class.below as u64) & !word;
+    for b in class.bytes {
+        let eq = word ^ (LO_BITS * b as u64);
+        mask |= eq.wrapping_sub(LO_BITS) & !eq;
+    }
+    mask & HI_BITS
+}
+
+/// Index of the first byte at or after `i` that needs escaping, or `bytes.len()`.
+///
+/// The scan checks a u64 word at a time and skips clean words in bulk.
+#[inline(always)]
+pub(crate) fn next_escape(bytes: &[u8], mut i: usize) -> usize {
+    let len = bytes.len();
+    while i + WORD_SIZE <= len {
+        let word = u64::from_le_bytes(bytes[i..i + WORD_SIZE].try_into().unwrap());
+        let mask = class_mask_swar(word, ByteClass::JSON);
+        if mask != 0 {
+            return i + (mask.trailing_zeros() / 8) as usize;
+        }
+        i += WORD_SIZE;
+    }
+    // Use lookup table for the tail shorter than a word
+    while i < len && ESCAPE[bytes[i] as usize] == 0 {
+        i += 1;
+    }
+    i
+}
+
+/// Index of the first byte at or after `i` that is in `class`, or `bytes.len()`.
+#[cfg(feature = "alloc")]
 #[inline]
-// Slightly modified version of
+pub(crate) fn next_in_class<const N: usize>(
+    bytes: &[u8],
+    mut i: usize,
+    class: ByteClass<N>,
+) -> usize {
+    let len = bytes.len();
+    while i + WORD_SIZE <= len {
+        let word = u64::from_le_bytes(bytes[i..i + WORD_SIZE].try_into().unwrap());
+        let mask = class_mask_swar(word, class);
+        if mask != 0 {
+            return i + (mask.trailing_zeros() / 8) as usize;
+        }
+        i += WORD_SIZE;
+    }
+    while i < len && !class.contains(bytes[i]) {
+        i += 1;
+    }
+    i
+}
+
+/// Copy `bytes` to `output`, handing the input from every byte in `class` on to `write` instead.
+///
+/// `write` returns how many bytes it consumed, at least one, so escapes can look ahead.
+#[cfg(feature = "alloc")]
+#[inline]
+pub(crate) fn escape_class<const N: usize>(
+    bytes: &[u8],
+    output: &mut Vec<u8>,
+    class: ByteClass<N>,
+    mut write: impl FnMut(&mut Vec<u8>, &[u8]) -> usize,
+) {
+    let backend = Backend::detect();
+    let mut start = 0;
+    loop {
+        let i = backend.find_in_class(bytes, start, class);
+        output.extend_from_slice(&bytes[start..i]);
+        if i == bytes.len() {
+            break;
+        }
+        start = i + write(output, &bytes[i..]);
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[inline]
+// Escape handling is a slightly modified version of
 // <https://github.com/serde-rs/json/blob/d12e943590208da738c092db92c34b39796a2538/src/ser.rs#L2079>
 // Borrowed from:
 // <https://github.com/oxc-project/oxc-sourcemap/blob/e533e6ca4d08c538d8d4df74eacd29437851591f/src/encode.rs#L331>
 pub(crate) fn escape_inner(bytes: &[u8], result: &mut Vec<u8>) {
+    let len = bytes.len();
     let mut start = 0;
-    let mut i = 0;
-
-    while i < bytes.len() {
-        let b = bytes[i];
-
-        // Use lookup table to check if escaping is needed
-        let escape_byte = ESCAPE[b as usize];
 
-        if escape_byte == 0 {
-            // No escape needed, continue scanning
-            i += 1;
-            continue;
-        }
+    loop {
+        let i = next_escape(bytes, start);
 
         // Copy any unescaped bytes before this position
         if start < i {
             result.extend_from_slice(&bytes[start..i]);
         }
 
-        // Handle the escape
-        result.push(b'\\');
-        if escape_byte == b'u' {
-            // Unicode escape for control characters
-            result.extend_from_slice(b"u00");
-            let hex_digits = &HEX_BYTES[b as usize];
-            result.push(hex_digits.0);
-            result.push(hex_digits.1);
-        } else {
-            // Simple escape
-            result.push(escape_byte);
+        if i >= len {
+            break;
+        }
+
+        write_escape(result, bytes[i]);
+        start = i + 1;
+    }
+}
+
+/// Length of the escaped `bytes`, without the surrounding quotes.
+#[inline]
+pub(crate) fn escaped_len_inner(bytes: &[u8]) -> usize {
+    let len = bytes.len();
+    let mut escaped_len = len;
+    let mut i = next_escape(bytes, 0);
+    while i < len {
+        // the escaped byte itself is already counted
+        escaped_len += ESCAPE_SEQ[bytes[i] as usize][ESCAPE_SEQ_SIZE - 1] as usize - 1;
+        i = next_escape(bytes, i + 1);
+    }
+    escaped_len
+}
+
+/// Escape `bytes` into `output`, without the surrounding quotes.
+///
+/// Returns the number of bytes written, or `None` if `output` is too short.
+#[inline]
+pub(crate) fn escape_slice_inner(bytes: &[u8], output: &mut [u8]) -> Option<usize> {
+    let len = bytes.len();
+    let mut start = 0;
+    let mut written = 0;
+
+    loop {
+        let i = next_escape(bytes, start);
+
+        // Copy any unescaped bytes before this position
+        let run = &bytes[start..i];
+        output
+            .get_mut(written..written + run.len())?
+            .copy_from_slice(run);
+        written += run.len();
+
+        if i >= len {
+            return Some(written);
         }
 
+        let seq = &ESCAPE_SEQ[bytes[i] as usize];
+        let seq_len = seq[ESCAPE_SEQ_SIZE - 1] as usize;
+        output
+            .get_mut(written..written + seq_len)?
+            .copy_from_slice(&seq[..seq_len]);
+        written += seq_len;
+        start = i + 1;
+    }
+}
+
+/// Length of the escaped `s` including the surrounding quotes, usable in `const` contexts.
+///
+/// This is the array length [`escape_const`] expects, see also [`json_str!`](crate::json_str).
+pub const fn escaped_len_const(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    let mut len = 2;
+    let mut i = 0;
+    while i < bytes.len() {
+        len += ESCAPE_SEQ[bytes[i] as usize][ESCAPE_SEQ_SIZE - 1] as usize;
         i += 1;
-        start = i;
     }
+    len
+}
+
+/// Escape `s` at compile time, surrounded with quotes.
+///
+/// `N` must be [`escaped_len_const(s)`](escaped_len_const), otherwise the evaluation panics. The
+/// [`json_str!`](crate::json_str) and [`json_key!`](crate::json_key) macros compute it for you.
+pub const fn escape_const<const N: usize>(s: &str) -> [u8; N] {
+    escape_const_with_suffix(s, b"")
+}
 
-    // Copy any remaining unescaped bytes
-    if start < bytes.len() {
-        result.extend_from_slice(&bytes[start..]);
+/// Escape `s` at compile time as an object key: quoted and followed by `:`.
+///
+/// `N` must be `escaped_len_const(s) + 1`, see [`json_key!`](crate::json_key).
+pub const fn escape_key_const<const N: usize>(s: &str) -> [u8; N] {
+    escape_const_with_suffix(s, b":")
+}
+
+const fn escape_const_with_suffix<const N: usize>(s: &str, suffix: &[u8]) -> [u8; N] {
+    assert!(
+        N == escaped_len_const(s) + suffix.len(),
+        "output length must match the escaped length"
+    );
+    let bytes = s.as_bytes();
+    let mut out = [0u8; N];
+    out[0] = b'"';
+    let mut written = 1;
+    let mut i = 0;
+    while i < bytes.len() {
+        let seq = &ESCAPE_SEQ[bytes[i] as usize];
+        let mut j = 0;
+        while j < seq[ESCAPE_SEQ_SIZE - 1] as usize {
+            out[written] = seq[j];
+            written += 1;
+            j += 1;
+        }
+        i += 1;
+    }
+    out[written] = b'"';
+    written += 1;
+    let mut k = 0;
+    while k < suffix.len() {
+        out[written + k] = suffix[k];
+        k += 1;
     }
+    out
 }
 
 const BB: u8 = b'b'; // \x08
@@ -103,6 +319,67 @@ pub(crate) static ESCAPE: [u8; 256] = [
     __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, __, // F
 ];
 
+/// Append the escape sequence of `c` to `result` with a single 8-byte store from [`ESCAPE_SEQ`].
+#[cfg(feature = "alloc")]
+#[inline(always)]
+pub(crate) fn write_escape(result: &mut Vec<u8>, c: u8) {
+    result.reserve(ESCAPE_SEQ_SIZE);
+    // SAFETY: we reserved room for the whole entry, only the real sequence length is committed
+    unsafe {
+        let len = result.len();
+        let end = write_escape_ptr(result.as_mut_ptr().add(len), c);
+        result.set_len(len + end.offset_from(result.as_ptr().add(len)) as usize);
+    }
+}
+
+/// Write the escape sequence of `c` to `dst` and return the position right after it.
+///
+/// # Safety
+///
+/// `dst` must be valid for writes of [`ESCAPE_SEQ_SIZE`] bytes, the whole table entry is stored.
+#[cfg(feature = "alloc")]
+#[inline(always)]
+pub(crate) unsafe fn write_escape_ptr(dst: *mut u8, c: u8) -> *mut u8 {
+    let seq = &ESCAPE_SEQ[c as usize];
+    unsafe {
+        core::ptr::write_unaligned(dst as *mut [u8; ESCAPE_SEQ_SIZE], *seq);
+        dst.add(seq[ESCAPE_SEQ_SIZE - 1] as usize)
+    }
+}
+
+/// Longest escape sequence, `\u00XX`.
+pub(crate) const MAX_ESCAPE_LEN: usize = 6;
+pub(crate) const ESCAPE_SEQ_SIZE: usize = 8;
+
+// Fixed-width escape sequences. Entry i holds the escaped form of byte i, padded with zeros, and
+// its length in the last byte. Bytes that don't need escaping map to themselves with length 1.
+pub(crate) static ESCAPE_SEQ: [[u8; ESCAPE_SEQ_SIZE]; 256] = {
+    let mut table = [[0u8; ESCAPE_SEQ_SIZE]; 256];
+    let mut i = 0;
+    while i < 256 {
+        let escape_byte = ESCAPE[i];
+        table[i] = if escape_byte == 0 {
+            [i as u8, 0, 0, 0, 0, 0, 0, 1]
+        } else if escape_byte == UU {
+            let hex = &HEX_BYTES[i];
+            [
+                b'\\',
+                b'u',
+                b'0',
+                b'0',
+                hex.0,
+                hex.1,
+                0,
+                MAX_ESCAPE_LEN as u8,
+            ]
+        } else {
+            [b'\\', escape_byte, 0, 0, 0, 0, 0, 2]
+        };
+        i += 1;
+    }
+    table
+};
+
 // Pre-computed hex digit pairs for control characters
 pub(crate) struct HexPair(pub(crate) u8, pub(crate) u8);
 
@@ -0,0 +1,102 @@
+use crate::generic::{ByteClass, escape_class, write_escape};
+use crate::{String, Vec};
+
+/// First byte of U+2028 and U+2029 in UTF-8 (`E2 80 A8`, `E2 80 A9`).
+pub(crate) const SEPARATOR_LEAD: u8 = 0xE2;
+
+/// Quotes of a JavaScript string literal, see [`escape_js`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum JsQuote {
+    /// `'…'`
+    Single,
+    /// `"…"`
+    #[default]
+    Double,
+    /// `` `…` `` template literals, `${` is escaped so nothing gets interpolated.
+    Backtick,
+}
+
+impl JsQuote {
+    #[inline]
+    fn byte(self) -> u8 {
+        match self {
+            JsQuote::Single => b'\'',
+            JsQuote::Double => b'"',
+            JsQuote::Backtick => b'`',
+        }
+    }
+}
+
+/// Options for [`escape_js`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct JsOptions {
+    /// The quotes written around the literal, and escaped inside it.
+    pub quote: JsQuote,
+    /// Write `</script` as `<\/script` (in any case), so the literal can be inlined in an HTML
+    /// `<script>` element.
+    pub script_safe: bool,
+}
+
+/// Escape `input` as a quoted JavaScript string literal.
+///
+/// Besides the quote, `\` and control characters, U+2028 and U+2029 are always escaped, older
+/// engines reject them in string literals and they are line terminators in template literals.
+pub fn escape_js<S: AsRef<str>>(input: S, options: JsOptions) -> String {
+    let input = input.as_ref();
+    let mut output = Vec::with_capacity(input.len() + input.len() / 2 + 2);
+    escape_js_into(input, &mut output, options);
+    // SAFETY: only ASCII escape sequences were added, the rest is copied from a `str`
+    unsafe { String::from_utf8_unchecked(output) }
+}
+
+/// Same as [`escape_js`], appending to `output`.
+pub fn escape_js_into<S: AsRef<str>>(input: S, output: &mut Vec<u8>, options: JsOptions) {
+    let quote = options.quote.byte();
+    let template = options.quote == JsQuote::Backtick;
+    // slots that don't apply repeat `\`, the scanners compare against a fixed number of bytes
+    let class = ByteClass {
+        bytes: [
+            quote,
+            b'\\',
+            SEPARATOR_LEAD,
+            if template { b'$' } else { b'\\' },
+            if options.script_safe { b'<' } else { b'\\' },
+        ],
+        below: 0x20,
+    };
+
+    output.push(quote);
+    escape_class(input.as_ref().as_bytes(), output, class, |output, rest| {
+        match rest {
+            [b'\\', ..] => output.extend_from_slice(b"\\\\"),
+            [b, ..] if *b == quote => output.extend_from_slice(&[b'\\', quote]),
+            [b, ..] if *b < 0x20 => write_escape(output, *b),
+            [SEPARATOR_LEAD, 0x80, 0xA8, ..] => {
+                output.extend_from_slice(b"\\u2028");
+                return 3;
+            }
+            [SEPARATOR_LEAD, 0x80, 0xA9, ..] => {
+                output.extend_from_slice(b"\\u2029");
+                return 3;
+            }
+            [b'$', b'{', ..] if template => {
+                output.extend_from_slice(b"\\${");
+                return 2;
+            }
+            [b'<', b'/', tag @ ..]
+                if options.script_safe
+                    && tag
+                        .get(..6)
+                        .is_some_and(|tag| tag.eq_ignore_ascii_case(b"script")) =>
+            {
+                output.extend_from_slice(b"<\\/");
+                return 2;
+            }
+            // other characters starting with `E2`, `$` without `{` and other tags
+            [b, ..] => output.push(*b),
+            [] => unreachable!("`escape_class` passes the matched byte"),
+        }
+        1
+    });
+    output.push(quote);
+}
@@ -0,0 +1,70 @@
+use crate::generic::{ByteClass, ESCAPE, HEX_BYTES, UU, escape_class, write_escape};
+use crate::js::SEPARATOR_LEAD;
+use crate::{String, Vec};
+
+/// Quotes of a JSON5 string, see [`escape_json5`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Json5Quote {
+    /// `'…'`, `"` is left as is.
+    Single,
+    /// `"…"`, `'` is left as is.
+    #[default]
+    Double,
+}
+
+/// Options for [`escape_json5`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Json5Options {
+    /// The quotes written around the string, only this quote is escaped inside it.
+    pub quote: Json5Quote,
+    /// Write control characters without a short escape as `\xXX` instead of `\u00XX`.
+    pub hex_escapes: bool,
+}
+
+/// Escape `input` as a quoted JSON5 string.
+///
+/// U+2028 and U+2029 are escaped like the reference `JSON5.stringify` does, JSON5 allows them in
+/// strings but not every parser accepts them. With the default options the output is also valid
+/// JSON.
+pub fn escape_json5<S: AsRef<str>>(input: S, options: Json5Options) -> String {
+    let input = input.as_ref();
+    let mut output = Vec::with_capacity(input.len() + input.len() / 2 + 2);
+    escape_json5_into(input, &mut output, options);
+    // SAFETY: only ASCII escape sequences were added, the rest is copied from a `str`
+    unsafe { String::from_utf8_unchecked(output) }
+}
+
+/// Same as [`escape_json5`], appending to `output`.
+pub fn escape_json5_into<S: AsRef<str>>(input: S, output: &mut Vec<u8>, options: Json5Options) {
+    let quote = match options.quote {
+        Json5Quote::Single => b'\'',
+        Json5Quote::Double => b'"',
+    };
+    let class = ByteClass {
+        bytes: [quote, b'\\', SEPARATOR_LEAD],
+        below: 0x20,
+    };
+
+    output.push(quote);
+    escape_class(input.as_ref().as_bytes(), output, class, |output, rest| {
+        match rest {
+            [b'\\', ..] => output.extend_from_slice(b"\\\\"),
+            [b, ..] if *b == quote => output.extend_from_slice(&[b'\\', quote]),
+            [SEPARATOR_LEAD, 0x80, b @ (0xA8 | 0xA9), ..] => {
+                let escaped: &[u8] = if *b == 0xA8 { b"\\u2028" } else { b"\\u2029" };
+                output.extend_from_slice(escaped);
+                return 3;
+            }
+            // other characters starting with `E2`
+            [SEPARATOR_LEAD, ..] => output.push(SEPARATOR_LEAD),
+            [b, ..] if options.hex_escapes && ESCAPE[*b as usize] == UU => {
+                let hex = &HEX_BYTES[*b as usize];
+                output.extend_from_slice(&[b'\\', b'x', hex.0, hex.1]);
+            }
+            [b, ..] => write_escape(output, *b),
+            [] => unreachable!("`escape_class` passes the matched byte"),
+        }
+        1
+    });
+    output.push(quote);
+}
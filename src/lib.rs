@@ -10,6 +10,38 @@
 //!
 //! The `force_aarch64_neon` feature flag can be used to force use of the neon implementation on aarch64. This is useful for the benchmark.
 //!
+//! ## Features
+//!
+//! ### `no_std`
+//!
+//! The crate is `no_std` compatible. The `std` feature (on by default) enables runtime CPU feature detection and the `JSON_ESCAPE_SIMD_MAX_WIDTH` environment variable. With `default-features = false, features = ["alloc"]`, `escape` / `escape_into` dispatch on the target features enabled at compile time. Without `alloc`, `escaped_len` and `escape_into_slice` still escape into a caller-provided buffer.
+//!
+//! ### Kernels
+//!
+//! - `nightly` enables the `powerpc64` (VSX) and `s390x` (vector facility) kernels. They rely on unstable `std::arch` intrinsics, so a nightly toolchain is required.
+//! - `portable_simd` enables a `std::simd` kernel, used on architectures without a dedicated SIMD implementation. It is also exported as `escape_portable` / `escape_into_portable` for differential testing. A nightly toolchain is required.
+//! - On `x86_64`, `set_max_vector_width(MaxVectorWidth::Bits256)` or `JSON_ESCAPE_SIMD_MAX_WIDTH=256` keeps the dispatch away from 512-bit registers, which can lower the clock speed on some Skylake-SP and Ice Lake servers. AVX-512VL hosts then use a 256-bit kernel that still benefits from mask registers.
+//!
+//! ### Integrations
+//!
+//! - `serde` adds `Serializer` (plus `to_string` / `to_vec` / `to_writer`), a drop-in for `serde_json::Serializer` that formats everything through a `serde_json` `Formatter` but escapes strings and keys with the SIMD kernels.
+//! - `simd-json` adds `SimdJsonGenerator`, a `simd-json` `BaseGenerator` whose `write_string` and friends go through the SIMD kernels. There is no `sonic-rs` integration yet.
+//! - `napi` adds `escape_js_string` / `escape_js_string_to_buffer` for Node-API addons. They read a `JsString` as Latin-1 when it is ASCII and as UTF-16 otherwise, instead of copying it through a Rust `String`, and return a JS string or a `Buffer`. `escape_utf16_into` exposes the same UTF-16 escaper without `napi`.
+//! - `bytes` adds `escape_into_buf`, which writes straight into a `bytes::BufMut` such as `BytesMut`, including non-contiguous buffers.
+//! - `tokio` and `futures-io` add `escape_to_async_writer` / `escape_to_futures_writer`, which escape large inputs in 16 KiB chunks and await the writes in between, so memory use stays flat.
+//! - `rayon` adds `par_escape`, which splits multi-megabyte inputs at UTF-8 boundaries and escapes the pieces on the rayon thread pool.
+//!
+//! ### Writers and other formats
+//!
+//! - `json_key!` and `json_str!` escape string constants at compile time, e.g. `json_key!("name")` is the `&'static [u8]` `"name":`. They are built on the `escape_const` / `escape_key_const` const fns.
+//! - `JsonWriter` is a small streaming writer for whole documents (objects, arrays, numbers, booleans and `null`) with nesting validation. It writes into a `Vec<u8>`, or into an `io::Write` via `JsonWriter::from_writer`, and escapes keys and strings with the SIMD kernels.
+//! - `NdjsonWriter` writes newline-delimited JSON to an `io::Write`, one record per line. Records are built with a `JsonWriter` in a single reused buffer that is flushed at a size threshold, and `string_field_record` is a fast path for log lines like `{"msg":"..."}`.
+//! - `escape_json_pointer_segment` (RFC 6901, `~0`/`~1`) and `escape_jsonpath_key` (RFC 9535 bracket notation, `['key']`) reuse the vector scanners to build JSON Pointers and JSONPath expressions from arbitrary keys.
+//! - `escape_js` writes JavaScript string literals for code generators: single, double or backtick quotes, with `${` escaped in template literals, U+2028/U+2029 always escaped and `</script` optionally written as `<\/script`.
+//! - `escape_json5` writes JSON5 strings with a choice of single or double quotes, escaping only the chosen quote, and optionally `\xXX` instead of `\u00XX` for control characters.
+//! - `escape_canonical` writes strings in the canonical form of RFC 8785 (JCS) for signed payloads. Its output is guaranteed to stay the same across versions, even if `escape` gains options.
+//! - `find_escapes` returns an `EscapeIter` over the byte offsets and `EscapeClass` of every character that needs escaping, found with the same vector compares as the kernels, for linters, diff tools and custom writers.
+//!
 //! ## Benchmarks
 //!
 //! Numbers below come from `cargo bench` runs on GitHub Actions hardware. Criterion reports are summarized to make it easier to spot relative performance. "vs fastest" shows how much slower each implementation is compared to the fastest entry in the table (1.00× means fastest).
@@ -108,69 +140,206 @@
 //! | `escape v_jsonescape` | 21.09 ms     | 1.18×      |
 //! | `json-escape`         | 22.43 ms     | 1.25×      |
 
-#[cfg(target_arch = "aarch64")]
+#![cfg_attr(
+    all(target_arch = "powerpc64", feature = "nightly", feature = "alloc"),
+    feature(stdarch_powerpc, powerpc_target_feature)
+)]
+#![cfg_attr(
+    all(target_arch = "powerpc64", feature = "nightly", feature = "std"),
+    feature(stdarch_powerpc_feature_detection)
+)]
+#![cfg_attr(
+    all(target_arch = "s390x", feature = "nightly", feature = "alloc"),
+    feature(stdarch_s390x)
+)]
+#![cfg_attr(
+    all(feature = "portable_simd", feature = "alloc"),
+    feature(portable_simd)
+)]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+// Without `std` there is no runtime feature detection, the kernels are picked from the target
+// features enabled at compile time instead.
+#[cfg(all(target_arch = "x86_64", feature = "alloc", not(feature = "std")))]
+macro_rules! is_x86_feature_detected {
+    ($feature:tt) => {
+        cfg!(target_feature = $feature)
+    };
+}
+#[cfg(all(
+    target_arch = "aarch64",
+    feature = "alloc",
+    not(feature = "std"),
+    not(feature = "force_aarch64_neon")
+))]
+macro_rules! is_aarch64_feature_detected {
+    ($feature:tt) => {
+        cfg!(target_feature = $feature)
+    };
+}
+#[cfg(all(target_arch = "powerpc64", feature = "alloc", not(feature = "std")))]
+macro_rules! is_powerpc64_feature_detected {
+    ($feature:tt) => {
+        cfg!(target_feature = $feature)
+    };
+}
+#[cfg(all(target_arch = "s390x", feature = "alloc", not(feature = "std")))]
+macro_rules! is_s390x_feature_detected {
+    ($feature:tt) => {
+        cfg!(target_feature = $feature)
+    };
+}
+
+#[cfg(all(target_arch = "aarch64", feature = "alloc"))]
 mod aarch64;
+#[cfg(any(feature = "tokio", feature = "futures-io"))]
+mod async_io;
+#[cfg(all(
+    any(
+        target_arch = "x86_64",
+        all(
+            any(target_arch = "powerpc64", target_arch = "s390x"),
+            feature = "nightly"
+        )
+    ),
+    feature = "alloc"
+))]
+mod block;
+#[cfg(feature = "bytes")]
+mod buf;
+#[cfg(feature = "alloc")]
+mod canonical;
+mod dispatch;
+#[cfg(feature = "simd-json")]
+mod generator;
 mod generic;
-#[cfg(target_arch = "x86_64")]
+#[cfg(feature = "alloc")]
+mod js;
+#[cfg(feature = "alloc")]
+mod json5;
+#[cfg(feature = "std")]
+mod ndjson;
+#[cfg(feature = "napi")]
+mod node;
+#[cfg(feature = "rayon")]
+mod par;
+#[cfg(feature = "alloc")]
+mod path;
+#[cfg(all(feature = "portable_simd", feature = "alloc"))]
+mod portable;
+#[cfg(all(target_arch = "powerpc64", feature = "nightly", feature = "alloc"))]
+mod powerpc64;
+#[cfg(all(target_arch = "s390x", feature = "nightly", feature = "alloc"))]
+mod s390x;
+#[cfg(feature = "alloc")]
+mod scan;
+#[cfg(feature = "serde")]
+mod ser;
+#[cfg(all(
+    any(target_arch = "powerpc64", target_arch = "s390x"),
+    feature = "nightly",
+    feature = "alloc"
+))]
+mod vec128;
+#[cfg(feature = "alloc")]
+mod writer;
+#[cfg(all(target_arch = "x86_64", feature = "alloc"))]
 mod x86;
 
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
+#[cfg(feature = "alloc")]
+use core::ops::Range;
+#[cfg(all(
+    target_arch = "aarch64",
+    feature = "std",
+    not(feature = "force_aarch64_neon")
+))]
+use std::arch::is_aarch64_feature_detected;
+#[cfg(all(target_arch = "powerpc64", feature = "nightly", feature = "std"))]
+use std::arch::is_powerpc64_feature_detected;
+#[cfg(all(target_arch = "s390x", feature = "nightly", feature = "std"))]
+use std::arch::is_s390x_feature_detected;
+
+#[cfg(feature = "tokio")]
+pub use async_io::escape_to_async_writer;
+#[cfg(feature = "futures-io")]
+pub use async_io::escape_to_futures_writer;
+#[cfg(feature = "bytes")]
+pub use buf::escape_into_buf;
+#[cfg(feature = "alloc")]
+pub use canonical::{escape_canonical, escape_canonical_into};
+pub use dispatch::{MaxVectorWidth, max_vector_width, set_max_vector_width};
+#[cfg(feature = "simd-json")]
+pub use generator::SimdJsonGenerator;
+pub use generic::{escape_const, escape_key_const, escaped_len_const};
+#[cfg(feature = "alloc")]
 pub use generic::{escape_generic, escape_into_generic};
+#[cfg(feature = "alloc")]
+pub use js::{JsOptions, JsQuote, escape_js, escape_js_into};
+#[cfg(feature = "alloc")]
+pub use json5::{Json5Options, Json5Quote, escape_json5, escape_json5_into};
+#[cfg(feature = "std")]
+pub use ndjson::NdjsonWriter;
+#[cfg(feature = "napi")]
+pub use node::{escape_js_string, escape_js_string_to_buffer};
+#[cfg(feature = "rayon")]
+pub use par::par_escape;
+#[cfg(feature = "alloc")]
+pub use path::{
+    escape_json_pointer_segment, escape_json_pointer_segment_into, escape_jsonpath_key,
+    escape_jsonpath_key_into,
+};
+#[cfg(all(feature = "portable_simd", feature = "alloc"))]
+pub use portable::{escape_into_portable, escape_portable};
+#[cfg(feature = "alloc")]
+pub use scan::{EscapeClass, EscapeIter, find_escapes};
+#[cfg(feature = "serde")]
+pub use ser::{Compound, Serializer, to_string, to_vec, to_writer};
+#[cfg(feature = "std")]
+pub use writer::IoSink;
+#[cfg(feature = "alloc")]
+pub use writer::{JsonNumber, JsonSink, JsonWriter, WriteError};
+
+/// Escape a string literal or `&str` constant at compile time.
+///
+/// Expands to a `&'static [u8]` holding the quoted, escaped string, e.g. `json_str!("a\"b")` is
+/// `br#""a\"b""#`.
+#[macro_export]
+macro_rules! json_str {
+    ($s:expr) => {{
+        const LEN: usize = $crate::escaped_len_const($s);
+        const ESCAPED: [u8; LEN] = $crate::escape_const::<LEN>($s);
+        &ESCAPED as &'static [u8]
+    }};
+}
+
+/// Escape an object key at compile time.
+///
+/// Expands to a `&'static [u8]` holding the quoted, escaped key followed by `:`, so
+/// `json_key!("name")` is `br#""name":"#`. Handy for serializers that write the same field names
+/// over and over.
+#[macro_export]
+macro_rules! json_key {
+    ($s:expr) => {{
+        const LEN: usize = $crate::escaped_len_const($s) + 1;
+        const ESCAPED: [u8; LEN] = $crate::escape_key_const::<LEN>($s);
+        &ESCAPED as &'static [u8]
+    }};
+}
 
 /// Main entry point for JSON string escaping with SIMD acceleration
 /// If the platform is supported, the SIMD path will be used. Otherwise, the generic fallback will be used.
+#[cfg(feature = "alloc")]
 pub fn escape<S: AsRef<str>>(input: S) -> String {
-    #[cfg(not(feature = "force_aarch64_neon"))]
-    use generic::escape_inner;
-
     let mut result = Vec::with_capacity(input.as_ref().len() + input.as_ref().len() / 2 + 2);
     result.push(b'"');
     let s = input.as_ref();
     let bytes = s.as_bytes();
-    #[cfg(target_arch = "x86_64")]
-    {
-        let len = bytes.len();
-        // Runtime CPU feature detection for x86_64
-        if is_x86_feature_detected!("avx512f")
-            && is_x86_feature_detected!("avx512bw")
-            && len >= x86::LOOP_SIZE_AVX512
-        {
-            unsafe { x86::escape_avx512(bytes, &mut result) }
-        } else if is_x86_feature_detected!("avx2") && len >= x86::LOOP_SIZE_AVX2 {
-            unsafe { x86::escape_avx2(bytes, &mut result) }
-        } else if is_x86_feature_detected!("sse2")
-            && /* if len < 128, no need to use simd */
-            len >= x86::LOOP_SIZE_AVX2
-        {
-            unsafe { x86::escape_sse2(bytes, &mut result) }
-        } else {
-            escape_inner(bytes, &mut result);
-        }
-    }
-
-    #[cfg(target_arch = "aarch64")]
-    {
-        #[cfg(feature = "force_aarch64_neon")]
-        {
-            aarch64::escape_neon(bytes, &mut result);
-        }
-        #[cfg(not(feature = "force_aarch64_neon"))]
-        {
-            // on Apple M2 and later, the `bf16` feature is available
-            // it means they have more registers and can significantly benefit from the SIMD path
-            // TODO: add support for sve2 chips with wider registers
-            // github actions ubuntu-24.04-arm runner has 128 bits sve2 registers, it's not enough for the SIMD path
-            if cfg!(target_os = "macos") && std::arch::is_aarch64_feature_detected!("bf16") {
-                aarch64::escape_neon(bytes, &mut result);
-            } else {
-                escape_inner(bytes, &mut result);
-            }
-        }
-    }
-
-    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
-    {
-        escape_inner(bytes, &mut result);
-    }
+    escape_bytes(bytes, &mut result);
     result.push(b'"');
     // SAFETY: We only pushed valid UTF-8 bytes (original string bytes and ASCII escape sequences)
     unsafe { String::from_utf8_unchecked(result) }
@@ -178,67 +347,397 @@ pub fn escape<S: AsRef<str>>(input: S) -> String {
 
 /// Main entry point for JSON string escaping with SIMD acceleration
 /// If the platform is supported, the SIMD path will be used. Otherwise, the generic fallback will be used.
+#[cfg(feature = "alloc")]
 pub fn escape_into<S: AsRef<str>>(input: S, output: &mut Vec<u8>) {
-    #[cfg(not(feature = "force_aarch64_neon"))]
-    use generic::escape_inner;
-
     output.push(b'"');
     let s = input.as_ref();
     let bytes = s.as_bytes();
-    #[cfg(target_arch = "x86_64")]
-    {
-        let len = bytes.len();
-        // Runtime CPU feature detection for x86_64
-        if is_x86_feature_detected!("avx512f")
-            && is_x86_feature_detected!("avx512bw")
-            && len >= x86::LOOP_SIZE_AVX512
-        {
-            unsafe { x86::escape_avx512(bytes, output) }
-        } else if is_x86_feature_detected!("avx2") && len >= x86::LOOP_SIZE_AVX2 {
-            unsafe { x86::escape_avx2(bytes, output) }
-        } else if is_x86_feature_detected!("sse2")
-          && /* if len < 128, no need to use simd */
-          len >= x86::LOOP_SIZE_AVX2
-        {
-            unsafe { x86::escape_sse2(bytes, output) }
-        } else {
-            escape_inner(bytes, output);
+    escape_bytes(bytes, output);
+    output.push(b'"');
+}
+
+/// Length of the escaped `input`, including the surrounding quotes.
+///
+/// This is the exact number of bytes [`escape`] produces and [`escape_into_slice`] needs.
+pub fn escaped_len<S: AsRef<str>>(input: S) -> usize {
+    generic::escaped_len_inner(input.as_ref().as_bytes()) + 2
+}
+
+/// Escape `input` into `output`, surrounded with quotes, and return the number of bytes written.
+///
+/// Returns `None` if `output` is too short, see [`escaped_len`]. Uses the scalar kernel and is
+/// available without the `alloc` feature.
+pub fn escape_into_slice<S: AsRef<str>>(input: S, output: &mut [u8]) -> Option<usize> {
+    let bytes = input.as_ref().as_bytes();
+    let (quote, rest) = output.split_first_mut()?;
+    *quote = b'"';
+    let written = generic::escape_slice_inner(bytes, rest)?;
+    *rest.get_mut(written)? = b'"';
+    Some(written + 2)
+}
+
+/// Escape every item of `items` as a quoted JSON string and append them to `output`, joined by
+/// `separator`. With `","` this writes the body of a JSON array.
+///
+/// The kernels are resolved once for the whole batch and `output` is reserved for all items up
+/// front, which pays off for many short strings. `items` is walked twice, once to size the
+/// reservation.
+#[cfg(feature = "alloc")]
+pub fn escape_many_into<I, S>(items: I, output: &mut Vec<u8>, separator: &str)
+where
+    I: IntoIterator<Item = S>,
+    I::IntoIter: Clone,
+    S: AsRef<str>,
+{
+    escape_many(items.into_iter(), output, separator, |_| {});
+}
+
+/// Same as [`escape_many_into`], and return the range of `output` holding each quoted item.
+#[cfg(feature = "alloc")]
+pub fn escape_many_into_with_offsets<I, S>(
+    items: I,
+    output: &mut Vec<u8>,
+    separator: &str,
+) -> Vec<Range<usize>>
+where
+    I: IntoIterator<Item = S>,
+    I::IntoIter: Clone,
+    S: AsRef<str>,
+{
+    let items = items.into_iter();
+    let mut offsets = Vec::with_capacity(items.size_hint().0);
+    escape_many(items, output, separator, |range| offsets.push(range));
+    offsets
+}
+
+#[cfg(feature = "alloc")]
+#[inline]
+fn escape_many<I, S>(
+    items: I,
+    output: &mut Vec<u8>,
+    separator: &str,
+    mut on_item: impl FnMut(Range<usize>),
+) where
+    I: Iterator<Item = S> + Clone,
+    S: AsRef<str>,
+{
+    let (count, len) = items.clone().fold((0, 0), |(count, len), s| {
+        (count + 1, len + s.as_ref().len())
+    });
+    output.reserve(len + len / 2 + count * (separator.len() + 2));
+
+    let backend = Backend::detect();
+    for (i, s) in items.enumerate() {
+        if i > 0 {
+            output.extend_from_slice(separator.as_bytes());
         }
+        let start = output.len();
+        output.push(b'"');
+        backend.escape(s.as_ref().as_bytes(), output);
+        output.push(b'"');
+        on_item(start..output.len());
     }
+}
 
-    #[cfg(target_arch = "aarch64")]
-    {
-        #[cfg(feature = "force_aarch64_neon")]
-        {
-            aarch64::escape_neon(bytes, output);
+/// Escape UTF-16 `input`, e.g. the contents of a JavaScript string, and append it to `output` as
+/// quoted UTF-8 JSON.
+///
+/// Lone surrogates can't be represented in UTF-8, they are written as `\udXXX` escapes like
+/// `JSON.stringify` does. Well-formed runs are transcoded into a fixed-size stack chunk, ASCII
+/// eight units at a time, and each chunk goes through the SIMD kernels.
+#[cfg(feature = "alloc")]
+pub fn escape_utf16_into(input: &[u16], output: &mut Vec<u8>) {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    const CHUNK: usize = 1024;
+    // the ASCII fast path writes 8 bytes per step, the others at most 4
+    const STEP: usize = 8;
+
+    let backend = Backend::detect();
+    let mut chunk = [0u8; CHUNK];
+    output.reserve(input.len() + 2);
+    output.push(b'"');
+    let mut i = 0;
+    while i < input.len() {
+        let mut n = 0;
+        while i < input.len() && n + STEP <= CHUNK {
+            if let Some(units) = input.get(i..i + STEP)
+                && units.iter().all(|&unit| unit < 0x80)
+            {
+                for (dst, &unit) in chunk[n..n + STEP].iter_mut().zip(units) {
+                    *dst = unit as u8;
+                }
+                n += STEP;
+                i += STEP;
+                continue;
+            }
+
+            let unit = input[i];
+            i += 1;
+            if unit < 0x80 {
+                chunk[n] = unit as u8;
+                n += 1;
+                continue;
+            }
+            match unit {
+                0x80..0x800 => {
+                    chunk[n] = 0xC0 | (unit >> 6) as u8;
+                    chunk[n + 1] = 0x80 | (unit & 0x3F) as u8;
+                    n += 2;
+                }
+                0xD800..0xDC00
+                    if input
+                        .get(i)
+                        .is_some_and(|low| (0xDC00..0xE000).contains(low)) =>
+                {
+                    let c = 0x10000 + ((unit as u32 - 0xD800) << 10) + (input[i] as u32 - 0xDC00);
+                    i += 1;
+                    chunk[n] = 0xF0 | (c >> 18) as u8;
+                    chunk[n + 1] = 0x80 | (c >> 12 & 0x3F) as u8;
+                    chunk[n + 2] = 0x80 | (c >> 6 & 0x3F) as u8;
+                    chunk[n + 3] = 0x80 | (c & 0x3F) as u8;
+                    n += 4;
+                }
+                0xD800..0xE000 => {
+                    backend.escape(&chunk[..n], output);
+                    n = 0;
+                    output.extend_from_slice(&[
+                        b'\\',
+                        b'u',
+                        HEX[(unit >> 12) as usize],
+                        HEX[(unit >> 8 & 0xF) as usize],
+                        HEX[(unit >> 4 & 0xF) as usize],
+                        HEX[(unit & 0xF) as usize],
+                    ]);
+                }
+                _ => {
+                    chunk[n] = 0xE0 | (unit >> 12) as u8;
+                    chunk[n + 1] = 0x80 | (unit >> 6 & 0x3F) as u8;
+                    chunk[n + 2] = 0x80 | (unit & 0x3F) as u8;
+                    n += 3;
+                }
+            }
         }
-        #[cfg(not(feature = "force_aarch64_neon"))]
-        {
+        backend.escape(&chunk[..n], output);
+    }
+    output.push(b'"');
+}
+
+/// Pick the best kernel for the current CPU and input length, and append the escaped `bytes`
+/// (without the surrounding quotes) to `output`.
+#[cfg(feature = "alloc")]
+#[inline]
+fn escape_bytes(bytes: &[u8], output: &mut Vec<u8>) {
+    Backend::detect().escape(bytes, output);
+}
+
+/// Widest `x86_64` kernel family allowed on this CPU, see [`Backend`].
+#[cfg(all(target_arch = "x86_64", feature = "alloc"))]
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum X86Tier {
+    Avx512Vbmi2,
+    Avx512,
+    Avx512Vl,
+    Avx2,
+    Baseline,
+}
+
+/// The kernels available on the current CPU.
+///
+/// Resolving them once lets batch APIs skip the feature detection for every string, only the
+/// length-based choice is left to [`Backend::escape`].
+#[cfg(feature = "alloc")]
+#[derive(Clone, Copy)]
+struct Backend {
+    #[cfg(target_arch = "x86_64")]
+    tier: X86Tier,
+    #[cfg(target_arch = "x86_64")]
+    avx2: bool,
+    #[cfg(target_arch = "x86_64")]
+    ssse3: bool,
+    #[cfg(target_arch = "x86_64")]
+    sse2: bool,
+    #[cfg(all(target_arch = "aarch64", not(feature = "force_aarch64_neon")))]
+    neon: bool,
+    #[cfg(all(target_arch = "powerpc64", feature = "nightly"))]
+    vsx: bool,
+    #[cfg(all(target_arch = "s390x", feature = "nightly"))]
+    vector: bool,
+}
+
+#[cfg(feature = "alloc")]
+impl Backend {
+    #[inline]
+    // the compile-time feature checks are constants without `std`
+    #[cfg_attr(not(feature = "std"), allow(clippy::nonminimal_bool, clippy::eq_op))]
+    fn detect() -> Self {
+        Backend {
+            // Runtime CPU feature detection for x86_64
+            #[cfg(target_arch = "x86_64")]
+            tier: if max_vector_width() == MaxVectorWidth::Bits512
+                && is_x86_feature_detected!("avx512f")
+                && is_x86_feature_detected!("avx512bw")
+            {
+                if is_x86_feature_detected!("avx512vbmi2")
+                    && is_x86_feature_detected!("avx512vbmi")
+                    && is_x86_feature_detected!("bmi2")
+                    && is_x86_feature_detected!("popcnt")
+                {
+                    X86Tier::Avx512Vbmi2
+                } else {
+                    X86Tier::Avx512
+                }
+            } else if is_x86_feature_detected!("avx512vl") && is_x86_feature_detected!("avx512bw") {
+                X86Tier::Avx512Vl
+            } else if is_x86_feature_detected!("avx2") {
+                X86Tier::Avx2
+            } else {
+                X86Tier::Baseline
+            },
+            #[cfg(target_arch = "x86_64")]
+            avx2: is_x86_feature_detected!("avx2"),
+            #[cfg(target_arch = "x86_64")]
+            ssse3: is_x86_feature_detected!("ssse3"),
+            #[cfg(target_arch = "x86_64")]
+            sse2: is_x86_feature_detected!("sse2"),
             // on Apple M2 and later, the `bf16` feature is available
             // it means they have more registers and can significantly benefit from the SIMD path
             // TODO: add support for sve2 chips with wider registers
             // github actions ubuntu-24.04-arm runner has 128 bits sve2 registers, it's not enough for the SIMD path
-            if cfg!(target_os = "macos") && std::arch::is_aarch64_feature_detected!("bf16") {
+            #[cfg(all(target_arch = "aarch64", not(feature = "force_aarch64_neon")))]
+            neon: cfg!(target_os = "macos") && is_aarch64_feature_detected!("bf16"),
+            #[cfg(all(target_arch = "powerpc64", feature = "nightly"))]
+            vsx: is_powerpc64_feature_detected!("vsx"),
+            #[cfg(all(target_arch = "s390x", feature = "nightly"))]
+            vector: is_s390x_feature_detected!("vector"),
+        }
+    }
+
+    /// Append the escaped `bytes` (without the surrounding quotes) to `output` with the best
+    /// kernel for their length.
+    #[inline]
+    fn escape(self, bytes: &[u8], output: &mut Vec<u8>) {
+        #[cfg(target_arch = "x86_64")]
+        {
+            let len = bytes.len();
+            if matches!(self.tier, X86Tier::Avx512Vbmi2 | X86Tier::Avx512) {
+                if len < x86::LOOP_SIZE_AVX512 {
+                    // masked loads cover short inputs in one or a few compares
+                    unsafe { x86::escape_short_avx512(bytes, output) }
+                } else if self.tier == X86Tier::Avx512Vbmi2 {
+                    unsafe { x86::escape_avx512_vbmi2(bytes, output) }
+                } else {
+                    unsafe { x86::escape_avx512(bytes, output) }
+                }
+            } else if (x86::M128_VECTOR_SIZE..x86::LOOP_SIZE_AVX2).contains(&len) && self.ssse3 {
+                // mid-size inputs don't fill a main loop iteration of the wider kernels
+                unsafe { x86::escape_ssse3(bytes, output) }
+            } else if self.tier == X86Tier::Avx512Vl {
+                // capped at 256 bits, keep the mask registers without the 512-bit frequency penalty
+                unsafe { x86::escape_avx512vl(bytes, output) }
+            } else if self.tier == X86Tier::Avx2 && len >= x86::LOOP_SIZE_AVX2 {
+                unsafe { x86::escape_avx2(bytes, output) }
+            } else if self.ssse3 && len >= x86::M128_VECTOR_SIZE {
+                // Atom / Silvermont and other hosts without AVX2
+                unsafe { x86::escape_ssse3(bytes, output) }
+            } else if self.sse2 && len >= x86::LOOP_SIZE_AVX2 {
+                unsafe { x86::escape_sse2(bytes, output) }
+            } else {
+                generic::escape_inner(bytes, output);
+            }
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        {
+            #[cfg(feature = "force_aarch64_neon")]
+            {
                 aarch64::escape_neon(bytes, output);
+            }
+            #[cfg(not(feature = "force_aarch64_neon"))]
+            {
+                if self.neon {
+                    aarch64::escape_neon(bytes, output);
+                } else {
+                    generic::escape_inner(bytes, output);
+                }
+            }
+        }
+
+        #[cfg(all(target_arch = "powerpc64", feature = "nightly"))]
+        {
+            if self.vsx {
+                unsafe { powerpc64::escape_vsx(bytes, output) }
+            } else {
+                generic::escape_inner(bytes, output);
+            }
+        }
+
+        #[cfg(all(target_arch = "s390x", feature = "nightly"))]
+        {
+            if self.vector {
+                unsafe { s390x::escape_vector(bytes, output) }
             } else {
-                escape_inner(bytes, output);
+                generic::escape_inner(bytes, output);
             }
         }
+
+        #[cfg(not(any(
+            target_arch = "x86_64",
+            target_arch = "aarch64",
+            all(
+                any(target_arch = "powerpc64", target_arch = "s390x"),
+                feature = "nightly"
+            )
+        )))]
+        {
+            // No dedicated kernel for this architecture, use `std::simd` if it's enabled
+            #[cfg(feature = "portable_simd")]
+            portable::escape_portable_inner(bytes, output);
+            #[cfg(not(feature = "portable_simd"))]
+            generic::escape_inner(bytes, output);
+        }
     }
 
-    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
-    {
-        escape_into_generic(input, output);
+    /// Index of the first byte at or after `start` that is in `class`, or `bytes.len()`.
+    ///
+    /// Scanning only needs compares, so the vector paths are used on every CPU that has them.
+    #[inline]
+    fn find_in_class<const N: usize>(
+        self,
+        bytes: &[u8],
+        start: usize,
+        class: generic::ByteClass<N>,
+    ) -> usize {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if self.avx2 {
+                unsafe { x86::find_in_class_avx2(bytes, start, class) }
+            } else if self.sse2 {
+                unsafe { x86::find_in_class_sse2(bytes, start, class) }
+            } else {
+                generic::next_in_class(bytes, start, class)
+            }
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        {
+            aarch64::find_in_class_neon(bytes, start, class)
+        }
+
+        #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+        {
+            generic::next_in_class(bytes, start, class)
+        }
     }
-    output.push(b'"');
 }
 
+#[cfg(feature = "alloc")]
 #[test]
 fn test_escape_ascii_json_string() {
     let fixture = r#"abcdefghijklmnopqrstuvwxyz .*? hello world escape json string"#;
     assert_eq!(escape(fixture), serde_json::to_string(fixture).unwrap());
 }
 
+#[cfg(feature = "alloc")]
 #[test]
 fn test_escape_json_string() {
     let mut fixture = String::new();
@@ -267,11 +766,13 @@ fn test_escape_json_string() {
 
 // Test cases for various string sizes to cover different SIMD paths
 
+#[cfg(feature = "alloc")]
 #[test]
 fn test_empty_string() {
     assert_eq!(escape(""), r#""""#);
 }
 
+#[cfg(feature = "alloc")]
 #[test]
 fn test_very_small_strings() {
     // Less than 16 bytes (SSE register size)
@@ -285,6 +786,7 @@ fn test_very_small_strings() {
     assert_eq!(escape("\r\n"), r#""\r\n""#);
 }
 
+#[cfg(feature = "alloc")]
 #[test]
 fn test_small_strings_16_bytes() {
     // Exactly 16 bytes - SSE register boundary
@@ -298,6 +800,7 @@ fn test_small_strings_16_bytes() {
     assert_eq!(escape(s16_esc), serde_json::to_string(s16_esc).unwrap());
 }
 
+#[cfg(feature = "alloc")]
 #[test]
 fn test_medium_strings_32_bytes() {
     // Exactly 32 bytes - AVX2 register boundary
@@ -310,6 +813,7 @@ fn test_medium_strings_32_bytes() {
     assert_eq!(escape(s32_esc), serde_json::to_string(s32_esc).unwrap());
 }
 
+#[cfg(feature = "alloc")]
 #[test]
 fn test_large_strings_128_bytes() {
     // Exactly 128 bytes - main loop size
@@ -329,6 +833,7 @@ fn test_large_strings_128_bytes() {
     assert_eq!(escape(&s128_esc), serde_json::to_string(&s128_esc).unwrap());
 }
 
+#[cfg(feature = "alloc")]
 #[test]
 fn test_unaligned_data() {
     // Test strings that start at various alignments
@@ -341,6 +846,7 @@ fn test_unaligned_data() {
     }
 }
 
+#[cfg(feature = "alloc")]
 #[test]
 fn test_sparse_escapes() {
     // Large string with escapes only at the beginning and end
@@ -351,6 +857,29 @@ fn test_sparse_escapes() {
     assert_eq!(escape(&s), serde_json::to_string(&s).unwrap());
 }
 
+#[cfg(feature = "alloc")]
+#[test]
+fn test_light_escapes_fit_initial_capacity() {
+    // Short keys with a few escapes shouldn't outgrow the capacity `escape` starts with
+    for len in [20, 32, 64, 100, 256] {
+        for every in [6, 30] {
+            let s: String = (0..len)
+                .map(|i| if i % every == every - 1 { '\n' } else { 'a' })
+                .collect();
+            let mut out = Vec::with_capacity(len + len / 2 + 2);
+            let (ptr, capacity) = (out.as_ptr(), out.capacity());
+            escape_into(&s, &mut out);
+            assert_eq!(out, serde_json::to_vec(&s).unwrap());
+            assert_eq!(
+                (out.as_ptr(), out.capacity()),
+                (ptr, capacity),
+                "len {len}, every {every}"
+            );
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
 #[test]
 fn test_dense_escapes() {
     // String with many escapes
@@ -367,6 +896,7 @@ fn test_dense_escapes() {
     assert_eq!(escape(&ctrl), serde_json::to_string(&ctrl).unwrap());
 }
 
+#[cfg(feature = "alloc")]
 #[test]
 fn test_boundary_conditions() {
     // Test around 256 byte boundary (common cache line multiple)
@@ -381,6 +911,7 @@ fn test_boundary_conditions() {
     }
 }
 
+#[cfg(feature = "alloc")]
 #[test]
 fn test_all_escape_types() {
     // Test each escape type individually
@@ -403,6 +934,7 @@ fn test_all_escape_types() {
     }
 }
 
+#[cfg(feature = "alloc")]
 #[test]
 fn test_mixed_content() {
     // Mix of ASCII, escapes, and multi-byte UTF-8
@@ -414,6 +946,7 @@ fn test_mixed_content() {
     assert_eq!(escape(mixed), serde_json::to_string(mixed).unwrap());
 }
 
+#[cfg(feature = "alloc")]
 #[test]
 fn test_repeated_patterns() {
     // Patterns that might benefit from or confuse SIMD operations
@@ -427,6 +960,7 @@ fn test_repeated_patterns() {
     assert_eq!(escape(&pattern3), serde_json::to_string(&pattern3).unwrap());
 }
 
+#[cfg(feature = "alloc")]
 #[test]
 fn test_rxjs() {
     let dir = glob::glob("node_modules/rxjs/src/**/*.ts").unwrap();
@@ -443,6 +977,7 @@ fn test_rxjs() {
     }
 }
 
+#[cfg(feature = "alloc")]
 #[test]
 fn test_sources() {
     let ts_paths = glob::glob("fixtures/**/*.ts").unwrap();
@@ -470,3 +1005,902 @@ fn test_sources() {
         assert_eq!(output, serde_json::to_string(&source).unwrap());
     }
 }
+
+#[cfg(feature = "portable_simd")]
+#[test]
+fn test_portable_simd_matches_generic() {
+    let mut fixtures = vec![
+        String::new(),
+        "a".repeat(31),
+        "a".repeat(33),
+        "\"\\\"\\\"\\\"\\".repeat(50),
+        "中文 English 🚀 \n❓ 𝄞".repeat(20),
+    ];
+    let mut ctrl = String::new();
+    for i in 0u8..=0x7F {
+        ctrl.push(i as char);
+    }
+    fixtures.push(ctrl);
+    for fixture in fixtures {
+        for offset in 0..fixture.len().min(64) {
+            if !fixture.is_char_boundary(offset) {
+                continue;
+            }
+            let s = &fixture[offset..];
+            assert_eq!(escape_portable(s), escape_generic(s));
+            let mut output = Vec::new();
+            escape_into_portable(s, &mut output);
+            assert_eq!(output, escape_generic(s).into_bytes());
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_generic_word_boundaries() {
+    // Every escapable byte at every position around the 8-byte SWAR word boundaries
+    for c in (0u8..0x20).chain(*b"\"\\") {
+        for len in 1..=24 {
+            for pos in 0..len {
+                let mut bytes = vec![b'a'; len];
+                bytes[pos] = c;
+                let s = String::from_utf8(bytes).unwrap();
+                assert_eq!(escape_generic(&s), serde_json::to_string(&s).unwrap());
+            }
+        }
+    }
+    // Bytes right above the ranges must not be flagged
+    let s = "!#[]^_`{|}~\u{7f}\u{80}ÿ€ ".repeat(9);
+    assert_eq!(escape_generic(&s), serde_json::to_string(&s).unwrap());
+}
+
+#[test]
+fn test_escape_into_slice() {
+    let mut fixtures = vec![
+        String::new(),
+        "a".repeat(20),
+        "中文 \"quoted\" \\ \t\u{1}\u{1f} ".repeat(10),
+    ];
+    let mut ctrl = String::new();
+    for i in 0u8..=0x7F {
+        ctrl.push(i as char);
+    }
+    fixtures.push(ctrl);
+    for fixture in &fixtures {
+        let expected = serde_json::to_string(fixture).unwrap();
+        assert_eq!(escaped_len(fixture), expected.len());
+
+        let mut buf = vec![0; expected.len() + 8];
+        let written = escape_into_slice(fixture, &mut buf).unwrap();
+        assert_eq!(&buf[..written], expected.as_bytes());
+        // every shorter buffer is rejected
+        for len in 0..expected.len() {
+            assert_eq!(escape_into_slice(fixture, &mut buf[..len]), None);
+        }
+        assert_eq!(
+            escape_into_slice(fixture, &mut buf[..expected.len()]),
+            Some(written)
+        );
+    }
+}
+
+#[test]
+fn test_escape_const() {
+    const KEY: &[u8] = json_key!("na\"me\u{1}");
+    assert_eq!(KEY, br#""na\"me\u0001":"#);
+    assert_eq!(json_str!(""), b"\"\"");
+
+    const ALL: &str = "\u{0}\u{8}\t\n\u{b}\u{c}\r\u{1f} \"\\/中文";
+    assert_eq!(
+        json_str!(ALL),
+        serde_json::to_string(ALL).unwrap().as_bytes()
+    );
+    assert_eq!(escaped_len_const(ALL), escaped_len(ALL));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serializer_matches_serde_json() {
+    use std::collections::BTreeMap;
+
+    let text = "中文 \"quoted\" \\ \t\u{1}\u{1f} ".repeat(20);
+    let value = serde_json::json!({
+        "a\"b": [1, -2.5, null, true, text, [], {}],
+        "nested": { "\u{7}": { "x": [[text.clone()], "é"] } },
+        text.clone(): 1u64 << 60,
+    });
+    assert_eq!(
+        to_string(&value).unwrap(),
+        serde_json::to_string(&value).unwrap()
+    );
+
+    let mut pretty = Vec::new();
+    serde::Serialize::serialize(&value, &mut Serializer::pretty(&mut pretty)).unwrap();
+    assert_eq!(pretty, serde_json::to_vec_pretty(&value).unwrap());
+
+    let keys: BTreeMap<i32, Result<char, (f64, f64)>> =
+        BTreeMap::from([(-1, Ok('"')), (7, Err((0.5, f64::NAN)))]);
+    assert_eq!(
+        to_string(&keys).unwrap(),
+        serde_json::to_string(&keys).unwrap()
+    );
+
+    let bad: BTreeMap<Vec<u8>, u8> = BTreeMap::from([(vec![1], 1)]);
+    assert!(to_string(&bad).is_err());
+}
+
+#[cfg(feature = "simd-json")]
+#[test]
+fn test_simd_json_generator_matches_native() {
+    use simd_json::prelude::*;
+
+    let mut fixtures = vec![
+        String::new(),
+        "中文 \"quoted\" \\ \t\u{1}\u{1f}\u{7f} ".repeat(20),
+        "a".repeat(300),
+    ];
+    let mut ctrl = String::new();
+    for i in 0u8..=0x7F {
+        ctrl.push(i as char);
+    }
+    fixtures.push(ctrl);
+
+    for fixture in &fixtures {
+        let native = simd_json::OwnedValue::from(fixture.as_str()).encode();
+        let mut generator = SimdJsonGenerator::new(Vec::new());
+        generator.write_string(fixture).unwrap();
+        generator.write_simple_string(fixture).unwrap();
+        generator.write_string_content(fixture).unwrap();
+        let out = generator.into_inner();
+        let expected = format!("{native}{native}{}", &native[1..native.len() - 1]);
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_escape_utf16() {
+    // spans several transcoding chunks
+    let fixture = "中文 é \"quoted\" \\ \t\u{1}\u{1f} 🦀".repeat(200);
+    let utf16: Vec<u16> = fixture.encode_utf16().collect();
+    let mut out = Vec::new();
+    escape_utf16_into(&utf16, &mut out);
+    assert_eq!(out, serde_json::to_vec(&fixture).unwrap());
+
+    // lone surrogates are escaped like `JSON.stringify` does
+    let mut lone = utf16.clone();
+    lone.insert(3, 0xD800);
+    lone.push(0xDFFF);
+    let mut out = Vec::new();
+    escape_utf16_into(&lone, &mut out);
+    let expected = serde_json::to_string(&fixture).unwrap();
+    let expected = format!(
+        "{}\\ud800{}\\udfff\"",
+        &expected[..8],
+        &expected[8..expected.len() - 1]
+    );
+    assert_eq!(String::from_utf8(out).unwrap(), expected);
+}
+
+#[cfg(all(feature = "bytes", feature = "alloc"))]
+#[test]
+fn test_escape_into_buf() {
+    use bytes::{BufMut, BytesMut};
+
+    let fixture = "中文 \"quoted\" \\ \t\u{1}\u{1f} ".repeat(8);
+    let expected = escape(&fixture);
+
+    let mut buf = BytesMut::with_capacity(4);
+    escape_into_buf(&fixture, &mut buf);
+    assert_eq!(&buf[..], expected.as_bytes());
+
+    // split the output across two chunks at every position, escapes straddle the boundary
+    let mut out = vec![0; expected.len()];
+    for split in 0..=expected.len() {
+        out.fill(0);
+        let (head, tail) = out.split_at_mut(split);
+        let mut chain = head.chain_mut(tail);
+        escape_into_buf(&fixture, &mut chain);
+        assert_eq!(chain.remaining_mut(), 0);
+        assert_eq!(out, expected.as_bytes());
+    }
+}
+
+/// In-memory writer that accepts at most 1000 bytes per call and is pending every other call.
+#[cfg(all(test, any(feature = "tokio", feature = "futures-io")))]
+#[derive(Default)]
+struct SlowWriter {
+    out: Vec<u8>,
+    pending: bool,
+}
+
+#[cfg(all(test, any(feature = "tokio", feature = "futures-io")))]
+impl SlowWriter {
+    fn poll_write(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        self.pending = !self.pending;
+        if self.pending {
+            cx.waker().wake_by_ref();
+            return std::task::Poll::Pending;
+        }
+        let n = buf.len().min(1000);
+        self.out.extend_from_slice(&buf[..n]);
+        std::task::Poll::Ready(Ok(n))
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+impl tokio::io::AsyncWrite for SlowWriter {
+    fn poll_write(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        self.get_mut().poll_write(cx, buf)
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(all(test, feature = "futures-io"))]
+impl futures_io::AsyncWrite for SlowWriter {
+    fn poll_write(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        self.get_mut().poll_write(cx, buf)
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(all(test, any(feature = "tokio", feature = "futures-io")))]
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+    loop {
+        if let std::task::Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+#[cfg(all(test, any(feature = "tokio", feature = "futures-io")))]
+#[test]
+fn test_escape_to_async_writer() {
+    // several chunks, with multi-byte characters and escapes across the chunk boundaries
+    let fixtures = [
+        String::new(),
+        "中文 \"quoted\" \\ \t\u{1}\u{1f} ".repeat(3000),
+    ];
+    for fixture in &fixtures {
+        let expected = escape(fixture);
+        #[cfg(feature = "tokio")]
+        {
+            let mut writer = SlowWriter::default();
+            block_on(escape_to_async_writer(fixture, &mut writer)).unwrap();
+            assert_eq!(writer.out, expected.as_bytes());
+        }
+        #[cfg(feature = "futures-io")]
+        {
+            let mut writer = SlowWriter::default();
+            block_on(escape_to_futures_writer(fixture, &mut writer)).unwrap();
+            assert_eq!(writer.out, expected.as_bytes());
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_par_escape() {
+    // the 3 and 4 byte characters end up across piece boundaries
+    let fixture = "中文 \"quoted\" \\ \t\u{1}\u{1f} 🦀".repeat(50_000);
+    assert!(fixture.len() > 1 << 20);
+    assert_eq!(par_escape(&fixture), escape(&fixture));
+    assert_eq!(par_escape("a\"b"), escape("a\"b"));
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_escape_many_into() {
+    let items = [
+        "",
+        "name",
+        "中文 \"quoted\" \\ \t\u{1}\u{1f}",
+        &"a\nb".repeat(100),
+    ];
+    let expected: Vec<String> = items.iter().map(escape).collect();
+
+    let mut output = b"[".to_vec();
+    escape_many_into(items, &mut output, ",");
+    output.push(b']');
+    assert_eq!(output, serde_json::to_vec(&items).unwrap());
+
+    let mut output = Vec::new();
+    let offsets = escape_many_into_with_offsets(items.iter(), &mut output, ", ");
+    assert_eq!(
+        String::from_utf8(output.clone()).unwrap(),
+        expected.join(", ")
+    );
+    assert_eq!(offsets.len(), items.len());
+    for (range, expected) in offsets.into_iter().zip(&expected) {
+        assert_eq!(&output[range], expected.as_bytes());
+    }
+
+    let mut output = Vec::new();
+    escape_many_into(Vec::<String>::new(), &mut output, ",");
+    assert!(output.is_empty());
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_json_writer() {
+    let text = "中文 \"quoted\" \\ \t\u{1}\u{1f} ".repeat(200);
+    let expected = serde_json::json!({
+        "a\"b": [1, -2, 2.5, 1e300, null, true, false, text, [], {}],
+        "nested": { "\u{7}": { "x": [[text.clone()], u64::MAX, i128::MIN.to_string()] } },
+    });
+
+    fn write<S: JsonSink>(w: &mut JsonWriter<S>, text: &str) -> Result<(), WriteError> {
+        w.begin_object()?;
+        w.key("a\"b")?;
+        w.begin_array()?;
+        w.number(1u8)?;
+        w.number(-2i64)?;
+        w.number(2.5f32)?;
+        w.number(1e300)?;
+        w.null()?;
+        w.bool(true)?;
+        w.bool(false)?;
+        w.string(text)?;
+        w.begin_array()?;
+        w.end_array()?;
+        w.begin_object()?;
+        w.end_object()?;
+        w.end_array()?;
+        w.key("nested")?;
+        w.begin_object()?;
+        w.key("\u{7}")?;
+        w.begin_object()?;
+        w.key("x")?;
+        w.begin_array()?;
+        w.begin_array()?;
+        w.string(text)?;
+        w.end_array()?;
+        w.number(u64::MAX)?;
+        w.string(&i128::MIN.to_string())?;
+        w.end_array()?;
+        w.end_object()?;
+        w.end_object()?;
+        w.end_object()
+    }
+
+    let mut writer = JsonWriter::new();
+    write(&mut writer, &text).unwrap();
+    let out = writer.finish().unwrap();
+    assert_eq!(out, serde_json::to_vec(&expected).unwrap());
+
+    // larger than the 8 KiB buffer, so the sink flushes along the way
+    #[cfg(feature = "std")]
+    {
+        let mut writer = JsonWriter::from_writer(std::io::Cursor::new(Vec::new()));
+        write(&mut writer, &text).unwrap();
+        assert_eq!(writer.finish().unwrap().into_inner(), out);
+    }
+
+    let mut existing = b"data: ".to_vec();
+    let mut writer = JsonWriter::with_sink(&mut existing);
+    writer.string("x").unwrap();
+    writer.finish().unwrap();
+    assert_eq!(existing, b"data: \"x\"");
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_json_writer_nesting() {
+    let mut w = JsonWriter::new();
+    assert!(w.key("a").is_err());
+    assert!(w.end_array().is_err());
+    w.begin_object().unwrap();
+    assert!(w.string("no key").is_err());
+    assert!(w.end_array().is_err());
+    w.key("a").unwrap();
+    assert!(w.key("b").is_err());
+    assert!(w.end_object().is_err());
+    assert!(w.number(f64::NAN).is_err());
+    w.begin_array().unwrap();
+    assert!(w.end_object().is_err());
+    w.end_array().unwrap();
+    w.end_object().unwrap();
+    assert!(w.null().is_err());
+    assert_eq!(w.finish().unwrap(), br#"{"a":[]}"#);
+
+    let mut w = JsonWriter::new();
+    w.begin_array().unwrap();
+    assert!(w.finish().is_err());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_ndjson_writer() {
+    let messages = ["started", "line one\nline two", "中文 \"quoted\" \t\u{1}"];
+
+    // a tiny threshold writes out on every record
+    for threshold in [1, 1 << 16] {
+        let mut ndjson = NdjsonWriter::with_threshold(Vec::new(), threshold);
+        for (i, message) in messages.iter().enumerate() {
+            ndjson.string_record(message).unwrap();
+            ndjson.string_field_record("msg", message).unwrap();
+            ndjson
+                .record(|w| {
+                    w.begin_object()?;
+                    w.key("seq")?;
+                    w.number(i)?;
+                    w.key("tags")?;
+                    w.begin_array()?;
+                    w.string(message)?;
+                    w.end_array()?;
+                    w.end_object()
+                })
+                .unwrap();
+            // incomplete and invalid records are dropped
+            assert!(ndjson.record(|w| w.begin_array()).is_err());
+            assert!(ndjson.record(|w| w.key("x")).is_err());
+        }
+        let out = String::from_utf8(ndjson.into_inner().unwrap()).unwrap();
+
+        let mut lines = out.lines();
+        for (i, message) in messages.iter().enumerate() {
+            let expected = [
+                serde_json::json!(message),
+                serde_json::json!({ "msg": message }),
+                serde_json::json!({ "seq": i, "tags": [message] }),
+            ];
+            for expected in expected {
+                let line = lines.next().unwrap();
+                assert_eq!(line, serde_json::to_string(&expected).unwrap());
+            }
+        }
+        assert_eq!(lines.next(), None);
+        assert!(out.ends_with('\n'));
+    }
+
+    // dropping writes out what's still buffered
+    let mut out = Vec::new();
+    let mut ndjson = NdjsonWriter::new(&mut out);
+    ndjson.string_field_record("msg", "bye").unwrap();
+    drop(ndjson);
+    assert_eq!(out, b"{\"msg\":\"bye\"}\n");
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_escape_json_pointer_and_jsonpath() {
+    assert_eq!(escape_json_pointer_segment("a/b~c"), "a~1b~0c");
+    assert_eq!(escape_json_pointer_segment("~1"), "~01");
+    assert_eq!(escape_jsonpath_key("it's"), r"['it\'s']");
+    assert_eq!(escape_jsonpath_key("\"\\\n\u{1}"), r#"['"\\\n\u0001']"#);
+
+    let jsonpath = |key: &str| {
+        let mut expected = String::from("['");
+        for c in key.chars() {
+            match c {
+                '\'' => expected.push_str("\\'"),
+                '"' => expected.push('"'),
+                c if c == '\\' || c < ' ' => {
+                    let quoted = serde_json::to_string(&c.to_string()).unwrap();
+                    expected.push_str(&quoted[1..quoted.len() - 1]);
+                }
+                c => expected.push(c),
+            }
+        }
+        expected + "']"
+    };
+    // move every special byte through all positions of the vector blocks
+    for special in ["~", "/", "'", "\\", "\"", "\n", "\u{1f}", "中"] {
+        for pos in 0..80 {
+            let key = format!("{}{special}{}/", "a".repeat(pos), "b".repeat(80 - pos));
+            let pointer = escape_json_pointer_segment(&key);
+            assert_eq!(pointer, key.replace('~', "~0").replace('/', "~1"));
+            let value = serde_json::json!({ &key: pos });
+            assert_eq!(
+                value.pointer(&format!("/{pointer}")),
+                Some(&serde_json::json!(pos))
+            );
+            assert_eq!(escape_jsonpath_key(&key), jsonpath(&key));
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_escape_js() {
+    use oxc_allocator::Allocator;
+    use oxc_ast::ast::{Expression, Statement};
+    use oxc_parser::Parser;
+    use oxc_span::SourceType;
+
+    // parse `x = <literal>;` with a real JS parser and return the value of the literal
+    fn parse(literal: &str) -> String {
+        let allocator = Allocator::default();
+        let source = format!("x = {literal};");
+        let ret = Parser::new(&allocator, &source, SourceType::mjs()).parse();
+        assert!(ret.errors.is_empty(), "{literal}: {:?}", ret.errors);
+        let Statement::ExpressionStatement(statement) = &ret.program.body[0] else {
+            panic!("{literal}: not an expression");
+        };
+        let Expression::AssignmentExpression(assignment) = &statement.expression else {
+            panic!("{literal}: not an assignment");
+        };
+        match &assignment.right {
+            Expression::StringLiteral(string) => string.value.to_string(),
+            Expression::TemplateLiteral(template) => {
+                assert!(template.expressions.is_empty(), "{literal}: interpolated");
+                template.quasis[0].value.cooked.unwrap().to_string()
+            }
+            _ => panic!("{literal}: not a string"),
+        }
+    }
+
+    let single = JsOptions {
+        quote: JsQuote::Single,
+        ..Default::default()
+    };
+    let backtick = JsOptions {
+        quote: JsQuote::Backtick,
+        ..Default::default()
+    };
+    assert_eq!(escape_js(r#"it's "${x}""#, single), r#"'it\'s "${x}"'"#);
+    assert_eq!(escape_js(r#"it's "${x}""#, backtick), r#"`it's "\${x}"`"#);
+    assert_eq!(
+        escape_js("a\u{2028}b", JsOptions::default()),
+        r#""a\u2028b""#
+    );
+    let script_safe = JsOptions {
+        script_safe: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        escape_js("</SCRIPT></b>", script_safe),
+        r#""<\/SCRIPT></b>""#
+    );
+    assert_eq!(
+        escape_js("</script>", JsOptions::default()),
+        r#""</script>""#
+    );
+
+    let ascii: String = (0u8..128).map(char::from).collect();
+    let mut fixtures = vec![
+        String::new(),
+        ascii.clone(),
+        ascii.repeat(3),
+        "\u{2028}\u{2029} € ‚ 中文 🦀".repeat(5),
+        "$ $$ ${ $${ {$ `${`} </script </Script> </scrip <</script".repeat(5),
+    ];
+    // move every special character through all positions of the vector blocks
+    for special in ["'", "\"", "`", "\\", "\n", "\u{2028}", "${", "</script"] {
+        for pos in 0..40 {
+            fixtures.push(format!("{}{special}{}$", "a".repeat(pos), "€".repeat(20)));
+        }
+    }
+    for quote in [JsQuote::Single, JsQuote::Double, JsQuote::Backtick] {
+        for script_safe in [false, true] {
+            let options = JsOptions { quote, script_safe };
+            for fixture in &fixtures {
+                let escaped = escape_js(fixture, options);
+                assert_eq!(&parse(&escaped), fixture);
+                assert!(!escaped.contains(['\u{2028}', '\u{2029}']));
+                if script_safe {
+                    assert!(!escaped.to_ascii_lowercase().contains("</script"));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_escape_json5() {
+    let single = Json5Options {
+        quote: Json5Quote::Single,
+        ..Default::default()
+    };
+    let hex = Json5Options {
+        hex_escapes: true,
+        ..Default::default()
+    };
+    assert_eq!(escape_json5(r#"it's "x""#, single), r#"'it\'s "x"'"#);
+    assert_eq!(escape_json5("\u{1}\n", hex), r#""\x01\n""#);
+
+    let ascii: String = (0u8..128).map(char::from).collect();
+    let mut fixtures = vec![
+        String::new(),
+        ascii.repeat(3),
+        "中文 € 🦀 \u{2028}\u{2029}".repeat(10),
+    ];
+    // move every special character through all positions of the vector blocks
+    for special in ["'", "\"", "\\", "\u{1}", "\u{1f}", "\n"] {
+        for pos in 0..40 {
+            fixtures.push(format!("{}{special}{}", "a".repeat(pos), "中".repeat(20)));
+        }
+    }
+    for quote in [Json5Quote::Single, Json5Quote::Double] {
+        for hex_escapes in [false, true] {
+            let options = Json5Options { quote, hex_escapes };
+            for fixture in &fixtures {
+                let escaped = escape_json5(fixture, options);
+                assert_eq!(&::json5::from_str::<String>(&escaped).unwrap(), fixture);
+                if options == Json5Options::default() {
+                    assert_eq!(&serde_json::from_str::<String>(&escaped).unwrap(), fixture);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_escape_canonical() {
+    // RFC 8785, section 3.2.2.2, `"€$\u000F\u000aA'B"\\\\"\/"`
+    assert_eq!(
+        escape_canonical("€$\u{f}\nA'B\"\\\\\"/"),
+        r#""€$\u000f\nA'B\"\\\\\"/""#
+    );
+    // keys of the "weird" sample in the RFC's reference test data
+    for (key, canonical) in [
+        ("\u{20ac}", "\"€\""),
+        ("\r", r#""\r""#),
+        ("\n", r#""\n""#),
+        ("1", r#""1""#),
+        ("\u{80}", "\"\u{80}\""),
+        ("Control\u{7f}", "\"Control\u{7f}\""),
+        ("\u{1f602}", "\"😂\""),
+        ("\u{f6}", "\"ö\""),
+        ("\u{fb33}", "\"\u{fb33}\""),
+        ("</script>", r#""</script>""#),
+    ] {
+        assert_eq!(escape_canonical(key), canonical);
+    }
+
+    // RFC 8785 section 3.2.2.2: control characters use the short escapes where JSON has one and
+    // lowercase `\u00XX` otherwise
+    let controls: [(u8, &[u8]); 32] = [
+        (0x00, b"\"\\u0000\""),
+        (0x01, b"\"\\u0001\""),
+        (0x02, b"\"\\u0002\""),
+        (0x03, b"\"\\u0003\""),
+        (0x04, b"\"\\u0004\""),
+        (0x05, b"\"\\u0005\""),
+        (0x06, b"\"\\u0006\""),
+        (0x07, b"\"\\u0007\""),
+        (0x08, b"\"\\b\""),
+        (0x09, b"\"\\t\""),
+        (0x0a, b"\"\\n\""),
+        (0x0b, b"\"\\u000b\""),
+        (0x0c, b"\"\\f\""),
+        (0x0d, b"\"\\r\""),
+        (0x0e, b"\"\\u000e\""),
+        (0x0f, b"\"\\u000f\""),
+        (0x10, b"\"\\u0010\""),
+        (0x11, b"\"\\u0011\""),
+        (0x12, b"\"\\u0012\""),
+        (0x13, b"\"\\u0013\""),
+        (0x14, b"\"\\u0014\""),
+        (0x15, b"\"\\u0015\""),
+        (0x16, b"\"\\u0016\""),
+        (0x17, b"\"\\u0017\""),
+        (0x18, b"\"\\u0018\""),
+        (0x19, b"\"\\u0019\""),
+        (0x1a, b"\"\\u001a\""),
+        (0x1b, b"\"\\u001b\""),
+        (0x1c, b"\"\\u001c\""),
+        (0x1d, b"\"\\u001d\""),
+        (0x1e, b"\"\\u001e\""),
+        (0x1f, b"\"\\u001f\""),
+    ];
+    for (c, canonical) in controls {
+        let input = char::from(c).to_string();
+        assert_eq!(escape_canonical(&input).as_bytes(), canonical, "{c:#04x}");
+    }
+    // everything else, including DEL and the line / paragraph separators, is copied as UTF-8
+    assert_eq!(escape_canonical("\u{7f}").as_bytes(), b"\"\x7f\"");
+    assert_eq!(escape_canonical("\u{2028}").as_bytes(), b"\"\xe2\x80\xa8\"");
+    assert_eq!(escape_canonical("\u{2029}").as_bytes(), b"\"\xe2\x80\xa9\"");
+    let long = format!(
+        "{}\u{2028}\u{7f}\u{2029}{}",
+        "a".repeat(200),
+        "é".repeat(70)
+    );
+    assert_eq!(escape_canonical(&long), format!("\"{long}\""));
+
+    // every ASCII character, and the same at every position of the vector blocks
+    let expected = |c: char| match c {
+        '\u{8}' => r"\b".to_string(),
+        '\t' => r"\t".to_string(),
+        '\n' => r"\n".to_string(),
+        '\u{c}' => r"\f".to_string(),
+        '\r' => r"\r".to_string(),
+        '"' => r#"\""#.to_string(),
+        '\\' => r"\\".to_string(),
+        c if c < ' ' => format!(r"\u{:04x}", c as u32),
+        c => c.to_string(),
+    };
+    for c in (0u8..128).map(char::from) {
+        for pos in [0, 15, 31, 63, 100] {
+            let input = format!("{}{c}{}", "a".repeat(pos), "é".repeat(70));
+            let canonical = format!("\"{}{}{}\"", "a".repeat(pos), expected(c), "é".repeat(70));
+            assert_eq!(escape_canonical(&input), canonical);
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_find_escapes() {
+    let escapes: Vec<_> = find_escapes("a\"b\\c\nd\u{1}中\u{1f}").collect();
+    assert_eq!(
+        escapes,
+        [
+            (1, EscapeClass::Quote),
+            (3, EscapeClass::Backslash),
+            (5, EscapeClass::ShortControl),
+            (7, EscapeClass::Control),
+            (11, EscapeClass::Control),
+        ]
+    );
+    assert_eq!(find_escapes("").next(), None);
+
+    // the offsets match the bytes `escape` rewrites
+    let sources = [
+        "中文 \"quoted\" \\ \t\u{1}",
+        "a".repeat(100).as_str(),
+        "\n\r",
+    ]
+    .concat();
+    for len in [0, 1, 15, 16, 31, 32, 63, 64, 127, 128, 500] {
+        let input: String = sources.chars().cycle().take(len).collect();
+        let mut rebuilt = String::from("\"");
+        let mut start = 0;
+        for (i, _) in find_escapes(&input) {
+            rebuilt.push_str(&input[start..i]);
+            let escaped = escape(&input[i..i + 1]);
+            rebuilt.push_str(&escaped[1..escaped.len() - 1]);
+            start = i + 1;
+        }
+        rebuilt.push_str(&input[start..]);
+        rebuilt.push('"');
+        assert_eq!(rebuilt, escape(&input));
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_short_strings_all_lengths() {
+    // Cover the masked and overlapping short-input paths below the main loop thresholds
+    for len in 0..300 {
+        let s = "a".repeat(len);
+        assert_eq!(escape(&s), serde_json::to_string(&s).unwrap());
+        for pos in [0, len / 3, len / 2, len.saturating_sub(1)] {
+            if pos >= len {
+                continue;
+            }
+            for c in ['\n', '"', '\\', '\x1f'] {
+                let mut bytes = s.clone().into_bytes();
+                bytes[pos] = c as u8;
+                let s = String::from_utf8(bytes).unwrap();
+                assert_eq!(escape(&s), serde_json::to_string(&s).unwrap());
+            }
+        }
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", feature = "alloc"))]
+#[test]
+// the compile-time feature checks are constants without `std`
+#[cfg_attr(not(feature = "std"), allow(clippy::nonminimal_bool, clippy::eq_op))]
+fn test_x86_kernels_match_generic() {
+    let mut fixtures = vec![
+        "a".repeat(300),
+        "\"\\\"\\\"\\\"\\".repeat(50),
+        "中文 English 🚀 \n❓ 𝄞".repeat(20),
+        "a\tb\"c\u{1}d\\e\u{1f}é\r\n".repeat(30),
+        // dense blocks followed by sparse ones in the same loop iteration
+        format!("{}{}", "x\"".repeat(48), "xxxxxxxxxxxxxxx\\".repeat(24)),
+    ];
+    let mut ctrl = String::new();
+    for i in 0u8..=0x7F {
+        ctrl.push(i as char);
+    }
+    fixtures.push(ctrl.repeat(3));
+    for fixture in &fixtures {
+        for offset in 0..64 {
+            for end in [fixture.len(), fixture.len() - 1, fixture.len() / 2] {
+                if offset >= end
+                    || !fixture.is_char_boundary(offset)
+                    || !fixture.is_char_boundary(end)
+                {
+                    continue;
+                }
+                let bytes = &fixture.as_bytes()[offset..end];
+                let mut expected = Vec::new();
+                generic::escape_inner(bytes, &mut expected);
+                let check = |name: &str, kernel: unsafe fn(&[u8], &mut Vec<u8>)| {
+                    let mut output = Vec::new();
+                    unsafe { kernel(bytes, &mut output) };
+                    assert_eq!(output, expected, "{name} at {offset}..{end}");
+                };
+                if bytes.len() >= x86::M128_VECTOR_SIZE && is_x86_feature_detected!("ssse3") {
+                    check("ssse3", x86::escape_ssse3);
+                }
+                if bytes.len() >= x86::LOOP_SIZE_AVX2 {
+                    check("sse2", x86::escape_sse2);
+                    if is_x86_feature_detected!("avx2") {
+                        check("avx2", x86::escape_avx2);
+                    }
+                }
+                if is_x86_feature_detected!("avx512vl") && is_x86_feature_detected!("avx512bw") {
+                    check("avx512vl", x86::escape_avx512vl);
+                }
+                if is_x86_feature_detected!("avx512f") && is_x86_feature_detected!("avx512bw") {
+                    check("short_avx512", x86::escape_short_avx512);
+                    if bytes.len() >= x86::LOOP_SIZE_AVX512 {
+                        check("avx512", x86::escape_avx512);
+                    }
+                    if bytes.len() >= x86::LOOP_SIZE_AVX512
+                        && is_x86_feature_detected!("avx512vbmi2")
+                        && is_x86_feature_detected!("avx512vbmi")
+                        && is_x86_feature_detected!("bmi2")
+                        && is_x86_feature_detected!("popcnt")
+                    {
+                        check("avx512_vbmi2", x86::escape_avx512_vbmi2);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_max_vector_width() {
+    let fixture = "中文 \"quoted\" \\ \t\u{1} ".repeat(40);
+    let expected = serde_json::to_string(&fixture).unwrap();
+    // the output must not depend on the selected kernel
+    for width in [MaxVectorWidth::Bits256, MaxVectorWidth::Bits512] {
+        set_max_vector_width(width);
+        assert_eq!(max_vector_width(), width);
+        for end in (0..fixture.len()).filter(|&i| fixture.is_char_boundary(i)) {
+            let s = &fixture[..end];
+            assert_eq!(escape(s), serde_json::to_string(s).unwrap());
+        }
+        assert_eq!(escape(&fixture), expected);
+    }
+}
@@ -0,0 +1,124 @@
+use core::mem::ManuallyDrop;
+use core::ptr;
+use std::io;
+
+use crate::{Backend, JsonWriter, Vec, WriteError};
+
+/// Buffered bytes that trigger a write to the underlying writer.
+const DEFAULT_FLUSH_THRESHOLD: usize = 64 * 1024;
+
+/// Writer for newline-delimited JSON (NDJSON / JSON Lines), one record per line.
+///
+/// Records are built in a single reused buffer that is written out once it grows past the flush
+/// threshold, 64 KiB by default. Escaping guarantees that strings never contain a raw newline, so
+/// every record stays on its own line. Call [`NdjsonWriter::flush`] or
+/// [`NdjsonWriter::into_inner`] to write out the rest and see errors. Like
+/// [`BufWriter`](std::io::BufWriter), dropping the writer also writes out the buffer, ignoring
+/// errors.
+pub struct NdjsonWriter<W: io::Write> {
+    writer: W,
+    buf: Vec<u8>,
+    threshold: usize,
+    backend: Backend,
+}
+
+impl<W: io::Write> NdjsonWriter<W> {
+    /// Create a writer with the default 64 KiB flush threshold.
+    #[inline]
+    pub fn new(writer: W) -> Self {
+        NdjsonWriter::with_threshold(writer, DEFAULT_FLUSH_THRESHOLD)
+    }
+
+    /// Create a writer that writes out its buffer once it holds at least `threshold` bytes.
+    #[inline]
+    pub fn with_threshold(writer: W, threshold: usize) -> Self {
+        NdjsonWriter {
+            writer,
+            buf: Vec::with_capacity(threshold.min(DEFAULT_FLUSH_THRESHOLD) + 1024),
+            threshold,
+            backend: Backend::detect(),
+        }
+    }
+
+    /// Build one record with a [`JsonWriter`] and terminate it with a newline.
+    ///
+    /// If `build` fails or leaves the document incomplete, the partial record is discarded.
+    pub fn record<F>(&mut self, build: F) -> Result<(), WriteError>
+    where
+        F: FnOnce(&mut JsonWriter<&mut Vec<u8>>) -> Result<(), WriteError>,
+    {
+        let start = self.buf.len();
+        let mut writer = JsonWriter::with_backend(&mut self.buf, self.backend);
+        if let Err(err) = build(&mut writer).and_then(|()| writer.finish().map(drop)) {
+            self.buf.truncate(start);
+            return Err(err);
+        }
+        self.end_record()
+    }
+
+    /// Write a record that is a single JSON string, `"value"`.
+    #[inline]
+    pub fn string_record(&mut self, value: &str) -> Result<(), WriteError> {
+        self.buf.push(b'"');
+        self.backend.escape(value.as_bytes(), &mut self.buf);
+        self.buf.push(b'"');
+        self.end_record()
+    }
+
+    /// Write a record that is an object with a single string field, `{"key":"value"}`, as in log
+    /// messages.
+    #[inline]
+    pub fn string_field_record(&mut self, key: &str, value: &str) -> Result<(), WriteError> {
+        self.buf.extend_from_slice(b"{\"");
+        self.backend.escape(key.as_bytes(), &mut self.buf);
+        self.buf.extend_from_slice(b"\":\"");
+        self.backend.escape(value.as_bytes(), &mut self.buf);
+        self.buf.extend_from_slice(b"\"}");
+        self.end_record()
+    }
+
+    /// Write out the buffered records and flush the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.write_buf()?;
+        self.writer.flush()
+    }
+
+    /// Flush and return the underlying writer.
+    ///
+    /// On error the buffered records are dropped, they are not written again when the writer is
+    /// dropped.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        if let Err(err) = self.flush() {
+            self.buf.clear();
+            return Err(err);
+        }
+        let this = ManuallyDrop::new(self);
+        // SAFETY: `this` is never used or dropped again, so the writer and the buffer are moved
+        // out exactly once
+        let (writer, _buf) = unsafe { (ptr::read(&this.writer), ptr::read(&this.buf)) };
+        Ok(writer)
+    }
+
+    #[inline]
+    fn end_record(&mut self) -> Result<(), WriteError> {
+        self.buf.push(b'\n');
+        if self.buf.len() >= self.threshold {
+            self.write_buf()?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn write_buf(&mut self) -> io::Result<()> {
+        self.writer.write_all(&self.buf)?;
+        self.buf.clear();
+        Ok(())
+    }
+}
+
+impl<W: io::Write> Drop for NdjsonWriter<W> {
+    fn drop(&mut self) {
+        // best effort like `BufWriter`, `flush` and `into_inner` report errors
+        let _ = self.write_buf();
+    }
+}
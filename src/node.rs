@@ -0,0 +1,61 @@
+use napi::{Env, Error, JsBuffer, JsString, NapiRaw, Result, Status, sys};
+
+use crate::{String, Vec, escape_bytes, escape_utf16_into};
+
+/// Escape a JavaScript string and return the quoted JSON as a new JavaScript string.
+///
+/// The input is read as Latin-1 when it is ASCII and as UTF-16 otherwise, the engine's own string
+/// representations, instead of going through a `napi_get_value_string_utf8` copy and a Rust
+/// [`String`]. Lone surrogates are escaped like `JSON.stringify` does.
+pub fn escape_js_string(env: &Env, input: JsString) -> Result<JsString> {
+    let escaped = escape_js_string_bytes(env, input)?;
+    // SAFETY: `escape_bytes` and `escape_utf16_into` only produce valid UTF-8
+    env.create_string_from_std(unsafe { String::from_utf8_unchecked(escaped) })
+}
+
+/// Escape a JavaScript string and return the quoted JSON as a UTF-8 `Buffer`.
+///
+/// The escaped bytes are handed to the `Buffer` without another copy.
+pub fn escape_js_string_to_buffer(env: &Env, input: JsString) -> Result<JsBuffer> {
+    let escaped = escape_js_string_bytes(env, input)?;
+    Ok(env.create_buffer_with_data(escaped)?.into_raw())
+}
+
+#[inline]
+fn escape_js_string_bytes(env: &Env, input: JsString) -> Result<Vec<u8>> {
+    let len = input.utf16_len()?;
+    let mut escaped = Vec::with_capacity(len + len / 2 + 2);
+    if input.utf8_len()? == len {
+        // ASCII strings are stored one byte per character, the Latin-1 accessor copies them as is
+        // and the kernels run on the bytes without transcoding
+        let mut latin1 = Vec::<u8>::with_capacity(len + 1);
+        let mut written = 0;
+        // SAFETY: the buffer has room for `len` bytes and the NUL terminator
+        let status = unsafe {
+            sys::napi_get_value_string_latin1(
+                env.raw(),
+                input.raw(),
+                latin1.as_mut_ptr().cast(),
+                len + 1,
+                &mut written,
+            )
+        };
+        if status != sys::Status::napi_ok {
+            return Err(Error::from_status(Status::from(status)));
+        }
+        // SAFETY: N-API wrote `written` bytes, all ASCII
+        unsafe { latin1.set_len(written) };
+        escaped.push(b'"');
+        escape_bytes(&latin1, &mut escaped);
+        escaped.push(b'"');
+    } else {
+        let utf16 = input.into_utf16()?;
+        // the buffer ends with the NUL terminator written by N-API
+        let units = utf16
+            .as_slice()
+            .split_last()
+            .map_or(&[][..], |(_, units)| units);
+        escape_utf16_into(units, &mut escaped);
+    }
+    Ok(escaped)
+}
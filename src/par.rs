@@ -0,0 +1,54 @@
+use rayon::prelude::*;
+
+use crate::{String, Vec, escape, escape_bytes};
+
+/// Input bytes per piece, large enough that the task overhead is noise.
+const PAR_CHUNK_SIZE: usize = 256 * 1024;
+
+/// Escape `input` on the rayon thread pool, surrounded with quotes.
+///
+/// The input is split at UTF-8 boundaries into pieces of about 256 KiB, every piece is escaped
+/// through the SIMD kernels into its own buffer, and the buffers are concatenated into an exactly
+/// sized output. Inputs of a single piece are escaped on the calling thread like [`escape`].
+pub fn par_escape<S: AsRef<str>>(input: S) -> String {
+    let s = input.as_ref();
+    if s.len() <= PAR_CHUNK_SIZE {
+        return escape(s);
+    }
+
+    let pieces: Vec<Vec<u8>> = split_pieces(s)
+        .into_par_iter()
+        .map(|piece| {
+            let mut buf = Vec::with_capacity(piece.len() + piece.len() / 8);
+            escape_bytes(piece.as_bytes(), &mut buf);
+            buf
+        })
+        .collect();
+
+    let len = pieces.iter().map(Vec::len).sum::<usize>() + 2;
+    let mut result = Vec::with_capacity(len);
+    result.push(b'"');
+    for piece in &pieces {
+        result.extend_from_slice(piece);
+    }
+    result.push(b'"');
+    // SAFETY: every piece is valid UTF-8 on its own, and escaping keeps it valid
+    unsafe { String::from_utf8_unchecked(result) }
+}
+
+fn split_pieces(s: &str) -> Vec<&str> {
+    let mut pieces = Vec::with_capacity(s.len() / PAR_CHUNK_SIZE + 1);
+    let mut rest = s;
+    while rest.len() > PAR_CHUNK_SIZE {
+        // a boundary is at most 3 bytes further
+        let mut at = PAR_CHUNK_SIZE;
+        while !rest.is_char_boundary(at) {
+            at += 1;
+        }
+        let (piece, tail) = rest.split_at(at);
+        pieces.push(piece);
+        rest = tail;
+    }
+    pieces.push(rest);
+    pieces
+}
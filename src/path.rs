@@ -0,0 +1,59 @@
+use crate::generic::{ByteClass, escape_class, write_escape};
+use crate::{String, Vec};
+
+/// Escape a JSON Pointer reference token (RFC 6901), `~` becomes `~0` and `/` becomes `~1`.
+///
+/// The result is a single segment without the leading `/`, so a pointer is built by joining
+/// escaped keys with `/`.
+pub fn escape_json_pointer_segment<S: AsRef<str>>(segment: S) -> String {
+    let segment = segment.as_ref();
+    let mut output = Vec::with_capacity(segment.len() + 2);
+    escape_json_pointer_segment_into(segment, &mut output);
+    // SAFETY: only ASCII bytes were swapped in, the rest is copied from a `str`
+    unsafe { String::from_utf8_unchecked(output) }
+}
+
+/// Same as [`escape_json_pointer_segment`], appending to `output`.
+pub fn escape_json_pointer_segment_into<S: AsRef<str>>(segment: S, output: &mut Vec<u8>) {
+    escape_class(
+        segment.as_ref().as_bytes(),
+        output,
+        ByteClass::JSON_POINTER,
+        |output, rest| {
+            let escaped: &[u8] = if rest[0] == b'~' { b"~0" } else { b"~1" };
+            output.extend_from_slice(escaped);
+            1
+        },
+    );
+}
+
+/// Escape `key` as a JSONPath name selector in bracket notation, e.g. `it's` becomes
+/// `['it\'s']`.
+///
+/// Follows the normalized path form of RFC 9535: `'` and `\` are escaped with a backslash,
+/// control characters like in JSON strings, and `"` is left as is.
+pub fn escape_jsonpath_key<S: AsRef<str>>(key: S) -> String {
+    let key = key.as_ref();
+    let mut output = Vec::with_capacity(key.len() + 6);
+    escape_jsonpath_key_into(key, &mut output);
+    // SAFETY: only ASCII escape sequences were added, the rest is copied from a `str`
+    unsafe { String::from_utf8_unchecked(output) }
+}
+
+/// Same as [`escape_jsonpath_key`], appending to `output`.
+pub fn escape_jsonpath_key_into<S: AsRef<str>>(key: S, output: &mut Vec<u8>) {
+    output.extend_from_slice(b"['");
+    escape_class(
+        key.as_ref().as_bytes(),
+        output,
+        ByteClass::JSONPATH,
+        |output, rest| {
+            match rest[0] {
+                b'\'' => output.extend_from_slice(b"\\'"),
+                b => write_escape(output, b),
+            }
+            1
+        },
+    );
+    output.extend_from_slice(b"']");
+}
@@ -0,0 +1,90 @@
+use core::simd::{
+    Simd,
+    cmp::{SimdPartialEq, SimdPartialOrd},
+};
+
+use alloc::{string::String, vec::Vec};
+
+use crate::generic::{ESCAPE, write_escape};
+
+const LANES: usize = 32;
+
+type Chunk = Simd<u8, LANES>;
+
+/// Escape a string with the [`std::simd`] kernel.
+///
+/// Available with the `portable_simd` feature. Useful as a reference for differential testing
+/// against the hand-written kernels.
+#[inline]
+pub fn escape_portable<S: AsRef<str>>(s: S) -> String {
+    let s = s.as_ref();
+    let bytes = s.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len() + bytes.len() / 2 + 2);
+    result.push(b'"');
+    escape_portable_inner(bytes, &mut result);
+    result.push(b'"');
+    // SAFETY: We only pushed valid UTF-8 bytes (original string bytes and ASCII escape sequences)
+    unsafe { String::from_utf8_unchecked(result) }
+}
+
+/// Escape a string with the [`std::simd`] kernel and append it to `output`.
+#[inline]
+pub fn escape_into_portable<S: AsRef<str>>(s: S, output: &mut Vec<u8>) {
+    let s = s.as_ref();
+    let bytes = s.as_bytes();
+    output.push(b'"');
+    escape_portable_inner(bytes, output);
+    output.push(b'"');
+}
+
+#[inline]
+pub(crate) fn escape_portable_inner(bytes: &[u8], result: &mut Vec<u8>) {
+    let len = bytes.len();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i + LANES <= len {
+        let mask = classify(Chunk::from_slice(&bytes[i..i + LANES]));
+        if mask != 0 {
+            process_mask(bytes, result, &mut start, i, mask);
+        }
+        i += LANES;
+    }
+
+    // Handle tail, the zero padding would classify as control characters so mask it off
+    if i < len {
+        let mask = classify(Chunk::load_or_default(&bytes[i..])) & ((1u64 << (len - i)) - 1);
+        if mask != 0 {
+            process_mask(bytes, result, &mut start, i, mask);
+        }
+    }
+
+    // Copy any remaining bytes
+    if start < len {
+        result.extend_from_slice(&bytes[start..]);
+    }
+}
+
+#[inline(always)]
+fn classify(v: Chunk) -> u64 {
+    let quote = v.simd_eq(Chunk::splat(b'"'));
+    let slash = v.simd_eq(Chunk::splat(b'\\'));
+    let ctrl = v.simd_lt(Chunk::splat(0x20));
+    (quote | slash | ctrl).to_bitmask()
+}
+
+#[inline(always)]
+fn process_mask(bytes: &[u8], result: &mut Vec<u8>, start: &mut usize, at: usize, mask: u64) {
+    let mut remaining = mask;
+    while remaining != 0 {
+        let i = at + remaining.trailing_zeros() as usize;
+        let c = bytes[i];
+        debug_assert!(ESCAPE[c as usize] != 0);
+        if *start < i {
+            result.extend_from_slice(&bytes[*start..i]);
+        }
+        write_escape(result, c);
+        *start = i + 1;
+        remaining &= remaining - 1;
+    }
+}
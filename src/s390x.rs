@@ -0,0 +1,32 @@
+use core::arch::s390x::{vec_cmpeq, vec_cmplt, vec_or, vec_splats, vec_xl, vector_unsigned_char};
+
+use alloc::vec::Vec;
+
+use crate::vec128::{VECTOR_SIZE, escape_128, lane_bits};
+
+/// Vector facility kernel for `s390x` (z13 and later).
+///
+/// Same compares as the VSX kernel, on the loop shared with it, see [`escape_128`].
+#[target_feature(enable = "vector")]
+#[inline]
+pub unsafe fn escape_vector(bytes: &[u8], output: &mut Vec<u8>) {
+    unsafe {
+        let v_quote: vector_unsigned_char = vec_splats(b'"');
+        let v_slash: vector_unsigned_char = vec_splats(b'\\');
+        let v_ctrl_limit: vector_unsigned_char = vec_splats(0x20u8);
+
+        escape_128(bytes, output, |ptr| {
+            let a: vector_unsigned_char = vec_xl(0, ptr);
+            // `vec_or` has no bool/bool overload, so widen the first compare to u8 lanes
+            let quote: vector_unsigned_char = core::mem::transmute(vec_cmpeq(a, v_quote));
+            let m = vec_or(
+                vec_or(quote, vec_cmpeq(a, v_slash)),
+                vec_cmplt(a, v_ctrl_limit),
+            );
+            lane_bits(core::mem::transmute::<
+                vector_unsigned_char,
+                [u8; VECTOR_SIZE],
+            >(m))
+        })
+    }
+}
@@ -0,0 +1,66 @@
+use core::iter::FusedIterator;
+
+use crate::Backend;
+use crate::generic::{ByteClass, ESCAPE, UU};
+
+/// Why a byte needs escaping in a JSON string, see [`find_escapes`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EscapeClass {
+    /// `"`, escaped as `\"`.
+    Quote,
+    /// `\`, escaped as `\\`.
+    Backslash,
+    /// A control character with a short escape, `\b \t \n \f \r`.
+    ShortControl,
+    /// Any other control character, escaped as `\u00XX`.
+    Control,
+}
+
+/// Iterator over the byte offsets and classes of the characters that need escaping in a JSON
+/// string, created by [`find_escapes`].
+#[derive(Clone)]
+pub struct EscapeIter<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    backend: Backend,
+}
+
+/// Find every character of `input` that needs escaping in a JSON string.
+///
+/// Yields the byte offset and [`EscapeClass`] of each one, in order. The input is scanned with the
+/// same vector compares as the kernels, so runs without escapes are skipped a block at a time.
+/// All escaped characters are ASCII, every offset is a `char` boundary.
+pub fn find_escapes(input: &str) -> EscapeIter<'_> {
+    EscapeIter {
+        bytes: input.as_bytes(),
+        pos: 0,
+        backend: Backend::detect(),
+    }
+}
+
+impl Iterator for EscapeIter<'_> {
+    type Item = (usize, EscapeClass);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let i = self
+            .backend
+            .find_in_class(self.bytes, self.pos, ByteClass::JSON);
+        let &b = self.bytes.get(i)?;
+        self.pos = i + 1;
+        let class = match b {
+            b'"' => EscapeClass::Quote,
+            b'\\' => EscapeClass::Backslash,
+            b if ESCAPE[b as usize] == UU => EscapeClass::Control,
+            _ => EscapeClass::ShortControl,
+        };
+        Some((i, class))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.bytes.len() - self.pos))
+    }
+}
+
+impl FusedIterator for EscapeIter<'_> {}
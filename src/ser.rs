@@ -0,0 +1,713 @@
+use core::num::FpCategory;
+use core::str;
+use std::{fmt::Display, io, string::ToString};
+
+use serde::ser::{self, Impossible, Serialize};
+use serde_json::{
+    Error,
+    ser::{CompactFormatter, Formatter, PrettyFormatter},
+};
+
+use crate::{String, Vec, escape_bytes};
+
+type Result<T> = core::result::Result<T, Error>;
+
+/// A [`serde_json`] compatible serializer whose string and key writes go through the SIMD escaper.
+///
+/// Structure, numbers and whitespace are delegated to a [`Formatter`], so the output is identical
+/// to `serde_json::Serializer::with_formatter` with the same formatter. Escaped strings are passed
+/// to [`Formatter::write_string_fragment`] in one piece.
+///
+/// `serde_json`'s `arbitrary_precision` numbers and `RawValue` rely on private tokens and are
+/// serialized as regular structs.
+pub struct Serializer<W, F = CompactFormatter> {
+    writer: W,
+    formatter: F,
+    scratch: Vec<u8>,
+}
+
+impl<W: io::Write> Serializer<W> {
+    /// Create a serializer producing compact JSON.
+    #[inline]
+    pub fn new(writer: W) -> Self {
+        Serializer::with_formatter(writer, CompactFormatter)
+    }
+}
+
+impl<'a, W: io::Write> Serializer<W, PrettyFormatter<'a>> {
+    /// Create a serializer producing pretty printed JSON.
+    #[inline]
+    pub fn pretty(writer: W) -> Self {
+        Serializer::with_formatter(writer, PrettyFormatter::new())
+    }
+}
+
+impl<W: io::Write, F: Formatter> Serializer<W, F> {
+    /// Create a serializer that formats everything but strings with `formatter`.
+    #[inline]
+    pub fn with_formatter(writer: W, formatter: F) -> Self {
+        Serializer {
+            writer,
+            formatter,
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Unwrap the writer.
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    #[inline]
+    fn write_str(&mut self, value: &str) -> io::Result<()> {
+        self.formatter.begin_string(&mut self.writer)?;
+        self.scratch.clear();
+        escape_bytes(value.as_bytes(), &mut self.scratch);
+        // SAFETY: escaping keeps valid UTF-8 valid
+        let escaped = unsafe { str::from_utf8_unchecked(&self.scratch) };
+        self.formatter
+            .write_string_fragment(&mut self.writer, escaped)?;
+        self.formatter.end_string(&mut self.writer)
+    }
+
+    #[inline]
+    fn write_variant_key(&mut self, variant: &str) -> io::Result<()> {
+        self.formatter.begin_object(&mut self.writer)?;
+        self.formatter.begin_object_key(&mut self.writer, true)?;
+        self.write_str(variant)?;
+        self.formatter.end_object_key(&mut self.writer)?;
+        self.formatter.begin_object_value(&mut self.writer)
+    }
+
+    #[inline]
+    fn end_variant(&mut self) -> io::Result<()> {
+        self.formatter.end_object_value(&mut self.writer)?;
+        self.formatter.end_object(&mut self.writer)
+    }
+}
+
+/// Serialize `value` as JSON into `writer`, see [`Serializer`].
+#[inline]
+pub fn to_writer<W: io::Write, T: ?Sized + Serialize>(writer: W, value: &T) -> Result<()> {
+    value.serialize(&mut Serializer::new(writer))
+}
+
+/// Serialize `value` as a JSON byte vector, see [`Serializer`].
+#[inline]
+pub fn to_vec<T: ?Sized + Serialize>(value: &T) -> Result<Vec<u8>> {
+    let mut writer = Vec::with_capacity(128);
+    to_writer(&mut writer, value)?;
+    Ok(writer)
+}
+
+/// Serialize `value` as a JSON string, see [`Serializer`].
+#[inline]
+pub fn to_string<T: ?Sized + Serialize>(value: &T) -> Result<String> {
+    let vec = to_vec(value)?;
+    // SAFETY: the serializer only emits valid UTF-8
+    Ok(unsafe { String::from_utf8_unchecked(vec) })
+}
+
+macro_rules! forward_number {
+    ($($method:ident: $ty:ty => $write:ident,)*) => {
+        $(
+            #[inline]
+            fn $method(self, value: $ty) -> Result<()> {
+                self.formatter.$write(&mut self.writer, value).map_err(Error::io)
+            }
+        )*
+    };
+}
+
+macro_rules! forward_float {
+    ($($method:ident: $ty:ty => $write:ident,)*) => {
+        $(
+            #[inline]
+            fn $method(self, value: $ty) -> Result<()> {
+                match value.classify() {
+                    FpCategory::Nan | FpCategory::Infinite => {
+                        self.formatter.write_null(&mut self.writer)
+                    }
+                    _ => self.formatter.$write(&mut self.writer, value),
+                }
+                .map_err(Error::io)
+            }
+        )*
+    };
+}
+
+impl<'a, W: io::Write, F: Formatter> ser::Serializer for &'a mut Serializer<W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Compound<'a, W, F>;
+    type SerializeTuple = Compound<'a, W, F>;
+    type SerializeTupleStruct = Compound<'a, W, F>;
+    type SerializeTupleVariant = Compound<'a, W, F>;
+    type SerializeMap = Compound<'a, W, F>;
+    type SerializeStruct = Compound<'a, W, F>;
+    type SerializeStructVariant = Compound<'a, W, F>;
+
+    #[inline]
+    fn serialize_bool(self, value: bool) -> Result<()> {
+        self.formatter
+            .write_bool(&mut self.writer, value)
+            .map_err(Error::io)
+    }
+
+    forward_number! {
+        serialize_i8: i8 => write_i8,
+        serialize_i16: i16 => write_i16,
+        serialize_i32: i32 => write_i32,
+        serialize_i64: i64 => write_i64,
+        serialize_i128: i128 => write_i128,
+        serialize_u8: u8 => write_u8,
+        serialize_u16: u16 => write_u16,
+        serialize_u32: u32 => write_u32,
+        serialize_u64: u64 => write_u64,
+        serialize_u128: u128 => write_u128,
+    }
+
+    forward_float! {
+        serialize_f32: f32 => write_f32,
+        serialize_f64: f64 => write_f64,
+    }
+
+    #[inline]
+    fn serialize_char(self, value: char) -> Result<()> {
+        self.write_str(value.encode_utf8(&mut [0; 4]))
+            .map_err(Error::io)
+    }
+
+    #[inline]
+    fn serialize_str(self, value: &str) -> Result<()> {
+        self.write_str(value).map_err(Error::io)
+    }
+
+    #[inline]
+    fn serialize_bytes(self, value: &[u8]) -> Result<()> {
+        self.formatter
+            .write_byte_array(&mut self.writer, value)
+            .map_err(Error::io)
+    }
+
+    #[inline]
+    fn serialize_none(self) -> Result<()> {
+        self.serialize_unit()
+    }
+
+    #[inline]
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<()> {
+        value.serialize(self)
+    }
+
+    #[inline]
+    fn serialize_unit(self) -> Result<()> {
+        self.formatter
+            .write_null(&mut self.writer)
+            .map_err(Error::io)
+    }
+
+    #[inline]
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        self.serialize_unit()
+    }
+
+    #[inline]
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<()> {
+        self.serialize_str(variant)
+    }
+
+    #[inline]
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        value.serialize(self)
+    }
+
+    #[inline]
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.write_variant_key(variant).map_err(Error::io)?;
+        value.serialize(&mut *self)?;
+        self.end_variant().map_err(Error::io)
+    }
+
+    #[inline]
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        self.formatter
+            .begin_array(&mut self.writer)
+            .map_err(Error::io)?;
+        let state = if len == Some(0) {
+            self.formatter
+                .end_array(&mut self.writer)
+                .map_err(Error::io)?;
+            State::Empty
+        } else {
+            State::First
+        };
+        Ok(Compound { ser: self, state })
+    }
+
+    #[inline]
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
+    }
+
+    #[inline]
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_seq(Some(len))
+    }
+
+    #[inline]
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        self.write_variant_key(variant).map_err(Error::io)?;
+        self.serialize_seq(Some(len))
+    }
+
+    #[inline]
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
+        self.formatter
+            .begin_object(&mut self.writer)
+            .map_err(Error::io)?;
+        let state = if len == Some(0) {
+            self.formatter
+                .end_object(&mut self.writer)
+                .map_err(Error::io)?;
+            State::Empty
+        } else {
+            State::First
+        };
+        Ok(Compound { ser: self, state })
+    }
+
+    #[inline]
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        self.serialize_map(Some(len))
+    }
+
+    #[inline]
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        self.write_variant_key(variant).map_err(Error::io)?;
+        self.serialize_map(Some(len))
+    }
+
+    fn collect_str<T: ?Sized + Display>(self, value: &T) -> Result<()> {
+        self.serialize_str(&value.to_string())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    Empty,
+    First,
+    Rest,
+}
+
+/// Serializer state for arrays and objects, returned by [`Serializer`].
+pub struct Compound<'a, W, F> {
+    ser: &'a mut Serializer<W, F>,
+    state: State,
+}
+
+impl<W: io::Write, F: Formatter> Compound<'_, W, F> {
+    #[inline]
+    fn element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        let ser = &mut *self.ser;
+        ser.formatter
+            .begin_array_value(&mut ser.writer, self.state == State::First)
+            .map_err(Error::io)?;
+        self.state = State::Rest;
+        value.serialize(&mut *ser)?;
+        ser.formatter
+            .end_array_value(&mut ser.writer)
+            .map_err(Error::io)
+    }
+
+    #[inline]
+    fn end_array(&mut self) -> Result<()> {
+        match self.state {
+            State::Empty => Ok(()),
+            _ => self
+                .ser
+                .formatter
+                .end_array(&mut self.ser.writer)
+                .map_err(Error::io),
+        }
+    }
+
+    #[inline]
+    fn key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<()> {
+        let ser = &mut *self.ser;
+        ser.formatter
+            .begin_object_key(&mut ser.writer, self.state == State::First)
+            .map_err(Error::io)?;
+        self.state = State::Rest;
+        key.serialize(MapKeySerializer { ser: &mut *ser })?;
+        ser.formatter
+            .end_object_key(&mut ser.writer)
+            .map_err(Error::io)
+    }
+
+    #[inline]
+    fn value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        let ser = &mut *self.ser;
+        ser.formatter
+            .begin_object_value(&mut ser.writer)
+            .map_err(Error::io)?;
+        value.serialize(&mut *ser)?;
+        ser.formatter
+            .end_object_value(&mut ser.writer)
+            .map_err(Error::io)
+    }
+
+    #[inline]
+    fn end_object(&mut self) -> Result<()> {
+        match self.state {
+            State::Empty => Ok(()),
+            _ => self
+                .ser
+                .formatter
+                .end_object(&mut self.ser.writer)
+                .map_err(Error::io),
+        }
+    }
+}
+
+impl<W: io::Write, F: Formatter> ser::SerializeSeq for Compound<'_, W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    #[inline]
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.element(value)
+    }
+
+    #[inline]
+    fn end(mut self) -> Result<()> {
+        self.end_array()
+    }
+}
+
+impl<W: io::Write, F: Formatter> ser::SerializeTuple for Compound<'_, W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    #[inline]
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.element(value)
+    }
+
+    #[inline]
+    fn end(mut self) -> Result<()> {
+        self.end_array()
+    }
+}
+
+impl<W: io::Write, F: Formatter> ser::SerializeTupleStruct for Compound<'_, W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    #[inline]
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.element(value)
+    }
+
+    #[inline]
+    fn end(mut self) -> Result<()> {
+        self.end_array()
+    }
+}
+
+impl<W: io::Write, F: Formatter> ser::SerializeTupleVariant for Compound<'_, W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    #[inline]
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.element(value)
+    }
+
+    #[inline]
+    fn end(mut self) -> Result<()> {
+        self.end_array()?;
+        self.ser.end_variant().map_err(Error::io)
+    }
+}
+
+impl<W: io::Write, F: Formatter> ser::SerializeMap for Compound<'_, W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    #[inline]
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<()> {
+        self.key(key)
+    }
+
+    #[inline]
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.value(value)
+    }
+
+    #[inline]
+    fn end(mut self) -> Result<()> {
+        self.end_object()
+    }
+}
+
+impl<W: io::Write, F: Formatter> ser::SerializeStruct for Compound<'_, W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    #[inline]
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.key(key)?;
+        self.value(value)
+    }
+
+    #[inline]
+    fn end(mut self) -> Result<()> {
+        self.end_object()
+    }
+}
+
+impl<W: io::Write, F: Formatter> ser::SerializeStructVariant for Compound<'_, W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    #[inline]
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.key(key)?;
+        self.value(value)
+    }
+
+    #[inline]
+    fn end(mut self) -> Result<()> {
+        self.end_object()?;
+        self.ser.end_variant().map_err(Error::io)
+    }
+}
+
+/// Object keys must be strings, scalars are quoted the same way `serde_json` does.
+struct MapKeySerializer<'a, W, F> {
+    ser: &'a mut Serializer<W, F>,
+}
+
+fn key_must_be_a_string() -> Error {
+    ser::Error::custom("key must be a string")
+}
+
+macro_rules! quoted_key {
+    ($($method:ident: $ty:ty => $write:ident,)*) => {
+        $(
+            #[inline]
+            fn $method(self, value: $ty) -> Result<()> {
+                let ser = self.ser;
+                ser.formatter.begin_string(&mut ser.writer).map_err(Error::io)?;
+                ser.formatter.$write(&mut ser.writer, value).map_err(Error::io)?;
+                ser.formatter.end_string(&mut ser.writer).map_err(Error::io)
+            }
+        )*
+    };
+}
+
+impl<W: io::Write, F: Formatter> ser::Serializer for MapKeySerializer<'_, W, F> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Impossible<(), Error>;
+    type SerializeTuple = Impossible<(), Error>;
+    type SerializeTupleStruct = Impossible<(), Error>;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = Impossible<(), Error>;
+    type SerializeStruct = Impossible<(), Error>;
+    type SerializeStructVariant = Impossible<(), Error>;
+
+    quoted_key! {
+        serialize_bool: bool => write_bool,
+        serialize_i8: i8 => write_i8,
+        serialize_i16: i16 => write_i16,
+        serialize_i32: i32 => write_i32,
+        serialize_i64: i64 => write_i64,
+        serialize_i128: i128 => write_i128,
+        serialize_u8: u8 => write_u8,
+        serialize_u16: u16 => write_u16,
+        serialize_u32: u32 => write_u32,
+        serialize_u64: u64 => write_u64,
+        serialize_u128: u128 => write_u128,
+    }
+
+    #[inline]
+    fn serialize_f32(self, value: f32) -> Result<()> {
+        if !value.is_finite() {
+            return Err(ser::Error::custom("float key must be finite"));
+        }
+        let ser = self.ser;
+        ser.formatter
+            .begin_string(&mut ser.writer)
+            .map_err(Error::io)?;
+        ser.formatter
+            .write_f32(&mut ser.writer, value)
+            .map_err(Error::io)?;
+        ser.formatter.end_string(&mut ser.writer).map_err(Error::io)
+    }
+
+    #[inline]
+    fn serialize_f64(self, value: f64) -> Result<()> {
+        if !value.is_finite() {
+            return Err(ser::Error::custom("float key must be finite"));
+        }
+        let ser = self.ser;
+        ser.formatter
+            .begin_string(&mut ser.writer)
+            .map_err(Error::io)?;
+        ser.formatter
+            .write_f64(&mut ser.writer, value)
+            .map_err(Error::io)?;
+        ser.formatter.end_string(&mut ser.writer).map_err(Error::io)
+    }
+
+    #[inline]
+    fn serialize_char(self, value: char) -> Result<()> {
+        self.ser.serialize_char(value)
+    }
+
+    #[inline]
+    fn serialize_str(self, value: &str) -> Result<()> {
+        self.ser.serialize_str(value)
+    }
+
+    fn serialize_bytes(self, _value: &[u8]) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+
+    #[inline]
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<()> {
+        self.ser.serialize_str(variant)
+    }
+
+    #[inline]
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<()> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(key_must_be_a_string())
+    }
+
+    fn collect_str<T: ?Sized + Display>(self, value: &T) -> Result<()> {
+        self.ser.collect_str(value)
+    }
+}
@@ -0,0 +1,70 @@
+use alloc::vec::Vec;
+
+use crate::block::{commit_block, escaped_extra, process_mask_bits, reserve_block};
+use crate::generic::escape_inner;
+
+const CHUNK: usize = 64;
+pub(crate) const VECTOR_SIZE: usize = 16;
+
+/// Loop shared by the 128-bit kernels of `powerpc64` and `s390x`.
+///
+/// `classify` returns the bitmask of bytes to escape among the [`VECTOR_SIZE`] bytes at a
+/// pointer, bit `i` for byte `i`. Each 64-byte chunk is classified with four calls, clean chunks
+/// are left pending and copied with the next escape, escapes are written into a reserved block
+/// with [`process_mask_bits`]. The tail shorter than a vector goes through [`escape_inner`].
+///
+/// Must be inlined into a function that enables the target features `classify` relies on.
+#[inline(always)]
+pub(crate) unsafe fn escape_128(
+    bytes: &[u8],
+    result: &mut Vec<u8>,
+    classify: impl Fn(*const u8) -> u16,
+) {
+    let len = bytes.len();
+    let mut start = 0;
+    let mut i = 0;
+
+    unsafe {
+        while i + CHUNK <= len {
+            let ptr = bytes.as_ptr().add(i);
+            let mask = classify(ptr) as u64
+                | (classify(ptr.add(VECTOR_SIZE)) as u64) << 16
+                | (classify(ptr.add(VECTOR_SIZE * 2)) as u64) << 32
+                | (classify(ptr.add(VECTOR_SIZE * 3)) as u64) << 48;
+            if mask != 0 {
+                let mut dst = reserve_block(result, i - start, CHUNK, || escaped_extra(ptr, mask));
+                dst = process_mask_bits(ptr, i, dst, &mut start, bytes, mask);
+                commit_block(result, dst);
+            }
+            i += CHUNK;
+        }
+
+        while i + VECTOR_SIZE <= len {
+            let ptr = bytes.as_ptr().add(i);
+            let mask = classify(ptr) as u64;
+            if mask != 0 {
+                let mut dst =
+                    reserve_block(result, i - start, VECTOR_SIZE, || escaped_extra(ptr, mask));
+                dst = process_mask_bits(ptr, i, dst, &mut start, bytes, mask);
+                commit_block(result, dst);
+            }
+            i += VECTOR_SIZE;
+        }
+    }
+
+    result.extend_from_slice(&bytes[start..i]);
+    escape_inner(&bytes[i..], result);
+}
+
+/// Pack a compare result, `0xFF` or `0x00` per lane, into one bit per lane.
+#[inline(always)]
+pub(crate) fn lane_bits(lanes: [u8; VECTOR_SIZE]) -> u16 {
+    // each lane is 0 or 1 after the mask, the multiply gathers the low bit of every byte into the
+    // top byte
+    let pack = |half: u64| {
+        ((half & 0x0101_0101_0101_0101).wrapping_mul(0x0102_0408_1020_4080) >> 56) as u16
+    };
+    let lo = u64::from_le_bytes(lanes[..8].try_into().unwrap());
+    let hi = u64::from_le_bytes(lanes[8..].try_into().unwrap());
+    pack(lo) | pack(hi) << 8
+}
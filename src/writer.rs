@@ -0,0 +1,416 @@
+use core::fmt::{self, Write as _};
+#[cfg(feature = "std")]
+use std::io;
+
+use crate::{Backend, Vec};
+
+/// Error returned by [`JsonWriter`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum WriteError {
+    /// The call doesn't fit the current position in the document, e.g. a value inside an object
+    /// without a key, or `end_array` closing an object.
+    Nesting(&'static str),
+    /// JSON has no representation for NaN and infinities.
+    NonFiniteNumber,
+    /// Writing to the underlying [`io::Write`] failed.
+    #[cfg(feature = "std")]
+    Io(io::Error),
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WriteError::Nesting(msg) => f.write_str(msg),
+            WriteError::NonFiniteNumber => f.write_str("JSON numbers must be finite"),
+            #[cfg(feature = "std")]
+            WriteError::Io(err) => err.fmt(f),
+        }
+    }
+}
+
+impl core::error::Error for WriteError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            WriteError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for WriteError {
+    fn from(err: io::Error) -> Self {
+        WriteError::Io(err)
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Output of a [`JsonWriter`]: `Vec<u8>`, `&mut Vec<u8>` or an [`IoSink`].
+pub trait JsonSink: sealed::Sealed {
+    /// What [`JsonWriter::finish`] returns.
+    type Inner;
+
+    #[doc(hidden)]
+    fn buf(&mut self) -> &mut Vec<u8>;
+
+    #[doc(hidden)]
+    fn flush_buf(&mut self, force: bool) -> Result<(), WriteError>;
+
+    #[doc(hidden)]
+    fn into_inner(self) -> Self::Inner;
+}
+
+impl sealed::Sealed for Vec<u8> {}
+
+impl JsonSink for Vec<u8> {
+    type Inner = Vec<u8>;
+
+    #[inline]
+    fn buf(&mut self) -> &mut Vec<u8> {
+        self
+    }
+
+    #[inline]
+    fn flush_buf(&mut self, _force: bool) -> Result<(), WriteError> {
+        Ok(())
+    }
+
+    #[inline]
+    fn into_inner(self) -> Vec<u8> {
+        self
+    }
+}
+
+impl sealed::Sealed for &mut Vec<u8> {}
+
+impl<'a> JsonSink for &'a mut Vec<u8> {
+    type Inner = &'a mut Vec<u8>;
+
+    #[inline]
+    fn buf(&mut self) -> &mut Vec<u8> {
+        self
+    }
+
+    #[inline]
+    fn flush_buf(&mut self, _force: bool) -> Result<(), WriteError> {
+        Ok(())
+    }
+
+    #[inline]
+    fn into_inner(self) -> &'a mut Vec<u8> {
+        self
+    }
+}
+
+/// Bytes buffered by an [`IoSink`] before they are written out.
+#[cfg(feature = "std")]
+const IO_BUFFER_SIZE: usize = 8 * 1024;
+
+/// [`JsonSink`] that buffers up to 8 KiB and writes it to an [`io::Write`].
+#[cfg(feature = "std")]
+pub struct IoSink<W> {
+    writer: W,
+    buf: Vec<u8>,
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> sealed::Sealed for IoSink<W> {}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> JsonSink for IoSink<W> {
+    type Inner = W;
+
+    #[inline]
+    fn buf(&mut self) -> &mut Vec<u8> {
+        &mut self.buf
+    }
+
+    #[inline]
+    fn flush_buf(&mut self, force: bool) -> Result<(), WriteError> {
+        if force || self.buf.len() >= IO_BUFFER_SIZE {
+            self.writer.write_all(&self.buf)?;
+            self.buf.clear();
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    /// Inside an array, `first` until the first element is written.
+    Array { first: bool },
+    /// Inside an object, `value` after a key until its value is written.
+    Object { first: bool, value: bool },
+}
+
+/// Streaming writer for compact JSON documents, with keys and strings escaped by the SIMD kernels.
+///
+/// The writer tracks the nesting and rejects calls that would produce invalid JSON with
+/// [`WriteError::Nesting`]. [`JsonWriter::finish`] checks that exactly one complete value was
+/// written.
+pub struct JsonWriter<S: JsonSink = Vec<u8>> {
+    sink: S,
+    stack: Vec<Scope>,
+    complete: bool,
+    backend: Backend,
+}
+
+impl JsonWriter<Vec<u8>> {
+    /// Create a writer into a new `Vec<u8>`.
+    #[inline]
+    pub fn new() -> Self {
+        JsonWriter::with_sink(Vec::new())
+    }
+}
+
+impl Default for JsonWriter<Vec<u8>> {
+    fn default() -> Self {
+        JsonWriter::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> JsonWriter<IoSink<W>> {
+    /// Create a writer that streams to `writer` in chunks of about 8 KiB.
+    #[inline]
+    pub fn from_writer(writer: W) -> Self {
+        JsonWriter::with_sink(IoSink {
+            writer,
+            buf: Vec::with_capacity(IO_BUFFER_SIZE),
+        })
+    }
+}
+
+impl<S: JsonSink> JsonWriter<S> {
+    /// Create a writer appending to `sink`, e.g. an existing `Vec<u8>` or `&mut Vec<u8>`.
+    #[inline]
+    pub fn with_sink(sink: S) -> Self {
+        JsonWriter::with_backend(sink, Backend::detect())
+    }
+
+    #[inline]
+    pub(crate) fn with_backend(sink: S, backend: Backend) -> Self {
+        JsonWriter {
+            sink,
+            stack: Vec::new(),
+            complete: false,
+            backend,
+        }
+    }
+
+    /// Open an object.
+    pub fn begin_object(&mut self) -> Result<(), WriteError> {
+        self.before_value()?;
+        self.sink.buf().push(b'{');
+        self.stack.push(Scope::Object {
+            first: true,
+            value: false,
+        });
+        Ok(())
+    }
+
+    /// Write an object key, the next call must write its value.
+    pub fn key(&mut self, key: &str) -> Result<(), WriteError> {
+        let Some(Scope::Object { first, value }) = self.stack.last_mut() else {
+            return Err(WriteError::Nesting("key outside of an object"));
+        };
+        if *value {
+            return Err(WriteError::Nesting("expected a value after the key"));
+        }
+        let buf = self.sink.buf();
+        if !*first {
+            buf.push(b',');
+        }
+        *first = false;
+        *value = true;
+        buf.push(b'"');
+        self.backend.escape(key.as_bytes(), buf);
+        buf.extend_from_slice(b"\":");
+        Ok(())
+    }
+
+    /// Close the innermost object.
+    pub fn end_object(&mut self) -> Result<(), WriteError> {
+        match self.stack.last() {
+            Some(Scope::Object { value: false, .. }) => {}
+            Some(Scope::Object { value: true, .. }) => {
+                return Err(WriteError::Nesting("expected a value after the key"));
+            }
+            _ => {
+                return Err(WriteError::Nesting(
+                    "end_object without a matching begin_object",
+                ));
+            }
+        }
+        self.stack.pop();
+        self.sink.buf().push(b'}');
+        self.after_value()
+    }
+
+    /// Open an array.
+    pub fn begin_array(&mut self) -> Result<(), WriteError> {
+        self.before_value()?;
+        self.sink.buf().push(b'[');
+        self.stack.push(Scope::Array { first: true });
+        Ok(())
+    }
+
+    /// Close the innermost array.
+    pub fn end_array(&mut self) -> Result<(), WriteError> {
+        if !matches!(self.stack.last(), Some(Scope::Array { .. })) {
+            return Err(WriteError::Nesting(
+                "end_array without a matching begin_array",
+            ));
+        }
+        self.stack.pop();
+        self.sink.buf().push(b']');
+        self.after_value()
+    }
+
+    /// Write a string value.
+    pub fn string(&mut self, value: &str) -> Result<(), WriteError> {
+        self.before_value()?;
+        let buf = self.sink.buf();
+        buf.push(b'"');
+        self.backend.escape(value.as_bytes(), buf);
+        buf.push(b'"');
+        self.after_value()
+    }
+
+    /// Write a number value, NaN and infinities are rejected.
+    pub fn number<N: JsonNumber>(&mut self, value: N) -> Result<(), WriteError> {
+        if !value.is_finite() {
+            return Err(WriteError::NonFiniteNumber);
+        }
+        self.before_value()?;
+        value.write(self.sink.buf());
+        self.after_value()
+    }
+
+    /// Write `true` or `false`.
+    pub fn bool(&mut self, value: bool) -> Result<(), WriteError> {
+        self.before_value()?;
+        let literal: &[u8] = if value { b"true" } else { b"false" };
+        self.sink.buf().extend_from_slice(literal);
+        self.after_value()
+    }
+
+    /// Write `null`.
+    pub fn null(&mut self) -> Result<(), WriteError> {
+        self.before_value()?;
+        self.sink.buf().extend_from_slice(b"null");
+        self.after_value()
+    }
+
+    /// Check that the document is complete, flush it and return the sink's output.
+    pub fn finish(mut self) -> Result<S::Inner, WriteError> {
+        if !self.complete {
+            return Err(WriteError::Nesting("the document is incomplete"));
+        }
+        self.sink.flush_buf(true)?;
+        Ok(self.sink.into_inner())
+    }
+
+    /// Separator before a value, and the checks that a value may go here.
+    #[inline]
+    fn before_value(&mut self) -> Result<(), WriteError> {
+        match self.stack.last_mut() {
+            None if self.complete => Err(WriteError::Nesting("the document is already complete")),
+            None => Ok(()),
+            Some(Scope::Array { first }) => {
+                if !*first {
+                    self.sink.buf().push(b',');
+                }
+                *first = false;
+                Ok(())
+            }
+            Some(Scope::Object { value, .. }) => {
+                if !*value {
+                    return Err(WriteError::Nesting("expected a key"));
+                }
+                *value = false;
+                Ok(())
+            }
+        }
+    }
+
+    #[inline]
+    fn after_value(&mut self) -> Result<(), WriteError> {
+        if self.stack.is_empty() {
+            self.complete = true;
+        }
+        self.sink.flush_buf(false)
+    }
+}
+
+/// Numbers accepted by [`JsonWriter::number`]: all primitive integers and floats.
+pub trait JsonNumber: sealed::Sealed + Copy {
+    #[doc(hidden)]
+    fn is_finite(self) -> bool;
+
+    #[doc(hidden)]
+    fn write(self, buf: &mut Vec<u8>);
+}
+
+/// `fmt::Write` adapter, integers and floats format through `core::fmt` without allocating.
+struct VecWriter<'a>(&'a mut Vec<u8>);
+
+impl fmt::Write for VecWriter<'_> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+}
+
+macro_rules! impl_json_number {
+    (int: $($ty:ty)*) => {
+        $(
+            impl sealed::Sealed for $ty {}
+
+            impl JsonNumber for $ty {
+                #[inline]
+                fn is_finite(self) -> bool {
+                    true
+                }
+
+                #[inline]
+                fn write(self, buf: &mut Vec<u8>) {
+                    let _ = write!(VecWriter(buf), "{self}");
+                }
+            }
+        )*
+    };
+    (float: $($ty:ty)*) => {
+        $(
+            impl sealed::Sealed for $ty {}
+
+            impl JsonNumber for $ty {
+                #[inline]
+                fn is_finite(self) -> bool {
+                    <$ty>::is_finite(self)
+                }
+
+                // `Debug` is the shortest round-trip form and switches to exponents for very large
+                // and small values, e.g. `1.0`, `0.1`, `1e21`, `1e-7`, all valid JSON
+                #[inline]
+                fn write(self, buf: &mut Vec<u8>) {
+                    let _ = write!(VecWriter(buf), "{self:?}");
+                }
+            }
+        )*
+    };
+}
+
+impl_json_number!(int: i8 i16 i32 i64 i128 isize u8 u16 u32 u64 u128 usize);
+impl_json_number!(float: f32 f64);
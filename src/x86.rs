@@ -1,28 +1,52 @@
 #![allow(unsafe_op_in_unsafe_fn)]
 
-use std::arch::x86_64::{
-    __m128i, __m256i, __m512i, _MM_HINT_T0, _mm_add_epi8, _mm_cmpeq_epi8, _mm_cmpgt_epi8,
-    _mm_load_si128, _mm_loadu_si128, _mm_movemask_epi8, _mm_or_si128, _mm_prefetch, _mm_set1_epi8,
-    _mm256_add_epi8, _mm256_cmpeq_epi8, _mm256_cmpgt_epi8, _mm256_load_si256, _mm256_loadu_si256,
-    _mm256_movemask_epi8, _mm256_or_si256, _mm256_set1_epi8, _mm512_cmpeq_epi8_mask,
-    _mm512_cmplt_epu8_mask, _mm512_load_si512, _mm512_loadu_si512, _mm512_set1_epi8,
+use core::arch::x86_64::{
+    __m128i, __m256i, __m512i, _MM_HINT_T0, _mm_add_epi8, _mm_and_si128, _mm_andnot_si128,
+    _mm_cmpeq_epi8, _mm_cmpgt_epi8, _mm_load_si128, _mm_loadu_si128, _mm_min_epu8,
+    _mm_movemask_epi8, _mm_or_si128, _mm_prefetch, _mm_set1_epi8, _mm_setr_epi8, _mm_setzero_si128,
+    _mm_shuffle_epi8, _mm_srli_epi16, _mm_storel_epi64, _mm_storeu_si128, _mm_sub_epi8,
+    _mm_unpackhi_epi8, _mm_unpackhi_epi16, _mm_unpackhi_epi32, _mm_unpackhi_epi64,
+    _mm_unpacklo_epi8, _mm_unpacklo_epi16, _mm_unpacklo_epi32, _mm256_add_epi8, _mm256_cmpeq_epi8,
+    _mm256_cmpeq_epi8_mask, _mm256_cmpgt_epi8, _mm256_cmplt_epu8_mask, _mm256_load_si256,
+    _mm256_loadu_si256, _mm256_mask_cmpeq_epi8_mask, _mm256_mask_cmplt_epu8_mask,
+    _mm256_maskz_loadu_epi8, _mm256_movemask_epi8, _mm256_or_si256, _mm256_set1_epi8,
+    _mm256_setzero_si256, _mm512_cmpeq_epi8_mask, _mm512_cmplt_epu8_mask, _mm512_load_si512,
+    _mm512_loadu_si512, _mm512_mask_blend_epi8, _mm512_mask_cmpeq_epi8_mask,
+    _mm512_mask_cmplt_epu8_mask, _mm512_mask_expand_epi8, _mm512_maskz_loadu_epi8,
+    _mm512_permutexvar_epi8, _mm512_set1_epi8, _mm512_shuffle_i64x2, _mm512_storeu_si512,
+    _pdep_u64, _pext_u64,
 };
 
-use crate::generic::{ESCAPE, HEX_BYTES, UU};
+use alloc::vec::Vec;
+
+use crate::block::{commit_block, copy_pending, escaped_extra, process_mask_bits, reserve_block};
+use crate::generic::{ByteClass, ESCAPE, UU, next_in_class};
 
 // Constants for control character detection using signed comparison trick
-const TRANSLATION_A: i8 = i8::MAX - 31i8;
-const BELOW_A: i8 = i8::MAX - (31i8 - 0i8) - 1;
-const B: i8 = 34i8; // '"'
-const C: i8 = 92i8; // '\\'
-
-const M512_VECTOR_SIZE: usize = std::mem::size_of::<__m512i>();
-const M256_VECTOR_SIZE: usize = std::mem::size_of::<__m256i>();
-const M128_VECTOR_SIZE: usize = std::mem::size_of::<__m128i>();
+const TRANSLATION_A: i8 = translation(ByteClass::JSON.below);
+const BELOW_A: i8 = threshold(ByteClass::JSON.below);
+const B: i8 = ByteClass::JSON.bytes[0] as i8; // '"'
+const C: i8 = ByteClass::JSON.bytes[1] as i8; // '\\'
+
+/// Offset that moves bytes below `below` to the top of the signed range, so a single signed
+/// compare against [`threshold`] matches them. With `below == 0` nothing is matched.
+const fn translation(below: u8) -> i8 {
+    (i8::MAX as u8).wrapping_sub(below).wrapping_add(1) as i8
+}
+
+const fn threshold(below: u8) -> i8 {
+    (i8::MAX as u8).wrapping_sub(below) as i8
+}
+
+const M512_VECTOR_SIZE: usize = core::mem::size_of::<__m512i>();
+const M256_VECTOR_SIZE: usize = core::mem::size_of::<__m256i>();
+pub(crate) const M128_VECTOR_SIZE: usize = core::mem::size_of::<__m128i>();
 pub(crate) const LOOP_SIZE_AVX2: usize = 4 * M256_VECTOR_SIZE; // Process 128 bytes at a time
 pub(crate) const LOOP_SIZE_AVX512: usize = 4 * M512_VECTOR_SIZE; // Process 256 bytes at a time
 const PREFETCH_DISTANCE_AVX2: usize = 256; // Prefetch 256 bytes ahead for AVX2
 const PREFETCH_DISTANCE_AVX512: usize = 512; // Prefetch 512 bytes ahead for AVX512
+const DENSE_ESCAPES: u32 = 4; // Escapes per 16-byte group before switching to the block emitter
+const DENSE_EXPAND: u32 = 8; // Escapes per 64-byte block before switching to `vpexpandb`
 
 #[inline(always)]
 fn sub(a: *const u8, b: *const u8) -> usize {
@@ -63,19 +87,9 @@ pub unsafe fn escape_avx512(bytes: &[u8], result: &mut Vec<u8>) {
 
         if mask != 0 {
             let at = sub(ptr, start_ptr);
-            while mask != 0 {
-                let cur = mask.trailing_zeros() as usize;
-                let c = *ptr.add(cur);
-                let escape_byte = ESCAPE[c as usize];
-                debug_assert!(escape_byte != 0);
-                let i = at + cur;
-                if start < i {
-                    result.extend_from_slice(&bytes[start..i]);
-                }
-                write_escape(result, escape_byte, c);
-                start = i + 1;
-                mask &= mask - 1;
-            }
+            let mut dst = reserve_block(result, at - start, align, || escaped_extra(ptr, mask));
+            dst = process_mask_bits(ptr, at, dst, &mut start, bytes, mask);
+            commit_block(result, dst);
         }
         ptr = ptr.add(align);
     }
@@ -126,42 +140,52 @@ pub unsafe fn escape_avx512(bytes: &[u8], result: &mut Vec<u8>) {
         let any_escape = mask_a | mask_b | mask_c | mask_d;
 
         if any_escape == 0 {
-            // No escapes needed, copy whole chunk
-            if start < sub(ptr, start_ptr) {
-                result.extend_from_slice(&bytes[start..sub(ptr, start_ptr)]);
-            }
-            result.extend_from_slice(std::slice::from_raw_parts(ptr, LOOP_SIZE_AVX512));
-            start = sub(ptr, start_ptr) + LOOP_SIZE_AVX512;
+            // No escapes needed, copy whole chunk along with any pending bytes
+            let end = sub(ptr, start_ptr) + LOOP_SIZE_AVX512;
+            result.extend_from_slice(&bytes[start..end]);
+            start = end;
         } else {
             // Process each 64-byte chunk that has escapes
-            process_mask_avx512(ptr, start_ptr, result, &mut start, bytes, mask_a, 0);
-            process_mask_avx512(
+            let mut dst = reserve_block(
+                result,
+                sub(ptr, start_ptr) - start,
+                LOOP_SIZE_AVX512,
+                || {
+                    escaped_extra(ptr, mask_a)
+                        + escaped_extra(ptr.add(M512_VECTOR_SIZE), mask_b)
+                        + escaped_extra(ptr.add(M512_VECTOR_SIZE * 2), mask_c)
+                        + escaped_extra(ptr.add(M512_VECTOR_SIZE * 3), mask_d)
+                },
+            );
+            dst = process_mask_avx512(ptr, start_ptr, dst, &mut start, bytes, mask_a, 0);
+            dst = process_mask_avx512(
                 ptr,
                 start_ptr,
-                result,
+                dst,
                 &mut start,
                 bytes,
                 mask_b,
                 M512_VECTOR_SIZE,
             );
-            process_mask_avx512(
+            dst = process_mask_avx512(
                 ptr,
                 start_ptr,
-                result,
+                dst,
                 &mut start,
                 bytes,
                 mask_c,
                 M512_VECTOR_SIZE * 2,
             );
-            process_mask_avx512(
+            dst = process_mask_avx512(
                 ptr,
                 start_ptr,
-                result,
+                dst,
                 &mut start,
                 bytes,
                 mask_d,
                 M512_VECTOR_SIZE * 3,
             );
+            commit_block(result, dst);
         }
 
         ptr = ptr.add(LOOP_SIZE_AVX512);
@@ -176,23 +200,15 @@ pub unsafe fn escape_avx512(bytes: &[u8], result: &mut Vec<u8>) {
         let slash_mask = _mm512_cmpeq_epi8_mask(a, v_c);
         let ctrl_mask = _mm512_cmplt_epu8_mask(a, v_ctrl_limit);
 
-        let mut mask = (quote_mask | slash_mask | ctrl_mask) as u64;
+        let mask = (quote_mask | slash_mask | ctrl_mask) as u64;
 
         if mask != 0 {
             let at = sub(ptr, start_ptr);
-            while mask != 0 {
-                let cur = mask.trailing_zeros() as usize;
-                let c = *ptr.add(cur);
-                let escape_byte = ESCAPE[c as usize];
-                debug_assert!(escape_byte != 0);
-                let i = at + cur;
-                if start < i {
-                    result.extend_from_slice(&bytes[start..i]);
-                }
-                write_escape(result, escape_byte, c);
-                start = i + 1;
-                mask &= mask - 1;
-            }
+            let mut dst = reserve_block(result, at - start, M512_VECTOR_SIZE, || {
+                escaped_extra(ptr, mask)
+            });
+            dst = process_mask_bits(ptr, at, dst, &mut start, bytes, mask);
+            commit_block(result, dst);
         }
         ptr = ptr.add(M512_VECTOR_SIZE);
     }
@@ -206,23 +222,15 @@ pub unsafe fn escape_avx512(bytes: &[u8], result: &mut Vec<u8>) {
         let slash_mask = _mm512_cmpeq_epi8_mask(a, v_c);
         let ctrl_mask = _mm512_cmplt_epu8_mask(a, v_ctrl_limit);
 
-        let mut mask = ((quote_mask | slash_mask | ctrl_mask) as u64).wrapping_shr(d as u32);
+        let mask = ((quote_mask | slash_mask | ctrl_mask) as u64).wrapping_shr(d as u32);
 
         if mask != 0 {
             let at = sub(ptr, start_ptr);
-            while mask != 0 {
-                let cur = mask.trailing_zeros() as usize;
-                let c = *ptr.add(cur);
-                let escape_byte = ESCAPE[c as usize];
-                debug_assert!(escape_byte != 0);
-                let i = at + cur;
-                if start < i {
-                    result.extend_from_slice(&bytes[start..i]);
-                }
-                write_escape(result, escape_byte, c);
-                start = i + 1;
-                mask &= mask - 1;
-            }
+            let mut dst = reserve_block(result, at - start, sub(end_ptr, ptr), || {
+                escaped_extra(ptr, mask)
+            });
+            dst = process_mask_bits(ptr, at, dst, &mut start, bytes, mask);
+            commit_block(result, dst);
         }
     }
 
@@ -252,7 +260,7 @@ pub unsafe fn escape_avx2(bytes: &[u8], result: &mut Vec<u8>) {
     let misalignment = start_ptr as usize & M256_VECTOR_ALIGN;
     if misalignment != 0 {
         let align = M256_VECTOR_SIZE - misalignment;
-        let mut mask = {
+        let mask = {
             let a = _mm256_loadu_si256(ptr as *const __m256i);
             _mm256_movemask_epi8(_mm256_or_si256(
                 _mm256_or_si256(_mm256_cmpeq_epi8(a, v_b), _mm256_cmpeq_epi8(a, v_c)),
@@ -260,26 +268,12 @@ pub unsafe fn escape_avx2(bytes: &[u8], result: &mut Vec<u8>) {
             ))
         };
 
+        let mask = (mask as u32 as u64) & ((1 << align) - 1);
         if mask != 0 {
             let at = sub(ptr, start_ptr);
-            let mut cur = mask.trailing_zeros() as usize;
-            while cur < align {
-                let c = *ptr.add(cur);
-                let escape_byte = ESCAPE[c as usize];
-                if escape_byte != 0 {
-                    let i = at + cur;
-                    if start < i {
-                        result.extend_from_slice(&bytes[start..i]);
-                    }
-                    write_escape(result, escape_byte, c);
-                    start = i + 1;
-                }
-                mask ^= 1 << cur;
-                if mask == 0 {
-                    break;
-                }
-                cur = mask.trailing_zeros() as usize;
-            }
+            let mut dst = reserve_block(result, at - start, align, || escaped_extra(ptr, mask));
+            dst = process_mask_bits(ptr, at, dst, &mut start, bytes, mask);
+            commit_block(result, dst);
         }
         ptr = ptr.add(align);
     }
@@ -331,12 +325,10 @@ pub unsafe fn escape_avx2(bytes: &[u8], result: &mut Vec<u8>) {
             _mm256_or_si256(_mm256_or_si256(cmp_a, cmp_b), _mm256_or_si256(cmp_c, cmp_d));
 
         if _mm256_movemask_epi8(any_escape) == 0 {
-            // No escapes needed, copy whole chunk
-            if start < sub(ptr, start_ptr) {
-                result.extend_from_slice(&bytes[start..sub(ptr, start_ptr)]);
-            }
-            result.extend_from_slice(std::slice::from_raw_parts(ptr, LOOP_SIZE_AVX2));
-            start = sub(ptr, start_ptr) + LOOP_SIZE_AVX2;
+            // No escapes needed, copy whole chunk along with any pending bytes
+            let end = sub(ptr, start_ptr) + LOOP_SIZE_AVX2;
+            result.extend_from_slice(&bytes[start..end]);
+            start = end;
         } else {
             // Get individual masks only when needed
             let mask_a = _mm256_movemask_epi8(cmp_a);
@@ -345,34 +337,42 @@ pub unsafe fn escape_avx2(bytes: &[u8], result: &mut Vec<u8>) {
             let mask_d = _mm256_movemask_epi8(cmp_d);
 
             // Process each 32-byte chunk that has escapes
-            process_mask_avx(ptr, start_ptr, result, &mut start, bytes, mask_a, 0);
-            process_mask_avx(
+            let mut dst =
+                reserve_block(result, sub(ptr, start_ptr) - start, LOOP_SIZE_AVX2, || {
+                    escaped_extra(ptr, mask_a as u32 as u64)
+                        + escaped_extra(ptr.add(M256_VECTOR_SIZE), mask_b as u32 as u64)
+                        + escaped_extra(ptr.add(M256_VECTOR_SIZE * 2), mask_c as u32 as u64)
+                        + escaped_extra(ptr.add(M256_VECTOR_SIZE * 3), mask_d as u32 as u64)
+                });
+            dst = process_mask_avx(ptr, start_ptr, dst, &mut start, bytes, mask_a, 0);
+            dst = process_mask_avx(
                 ptr,
                 start_ptr,
-                result,
+                dst,
                 &mut start,
                 bytes,
                 mask_b,
                 M256_VECTOR_SIZE,
             );
-            process_mask_avx(
+            dst = process_mask_avx(
                 ptr,
                 start_ptr,
-                result,
+                dst,
                 &mut start,
                 bytes,
                 mask_c,
                 M256_VECTOR_SIZE * 2,
             );
-            process_mask_avx(
+            dst = process_mask_avx(
                 ptr,
                 start_ptr,
-                result,
+                dst,
                 &mut start,
                 bytes,
                 mask_d,
                 M256_VECTOR_SIZE * 3,
             );
+            commit_block(result, dst);
         }
 
         ptr = ptr.add(LOOP_SIZE_AVX2);
@@ -381,7 +381,7 @@ pub unsafe fn escape_avx2(bytes: &[u8], result: &mut Vec<u8>) {
     // Process remaining aligned chunks
     while ptr <= end_ptr.sub(M256_VECTOR_SIZE) {
         debug_assert_eq!(0, (ptr as usize) % M256_VECTOR_SIZE);
-        let mut mask = {
+        let mask = {
             let a = _mm256_load_si256(ptr as *const __m256i);
             _mm256_movemask_epi8(_mm256_or_si256(
                 _mm256_or_si256(_mm256_cmpeq_epi8(a, v_b), _mm256_cmpeq_epi8(a, v_c)),
@@ -389,26 +389,14 @@ pub unsafe fn escape_avx2(bytes: &[u8], result: &mut Vec<u8>) {
             ))
         };
 
+        let mask = mask as u32 as u64;
         if mask != 0 {
             let at = sub(ptr, start_ptr);
-            let mut cur = mask.trailing_zeros() as usize;
-            loop {
-                let c = *ptr.add(cur);
-                let escape_byte = ESCAPE[c as usize];
-                if escape_byte != 0 {
-                    let i = at + cur;
-                    if start < i {
-                        result.extend_from_slice(&bytes[start..i]);
-                    }
-                    write_escape(result, escape_byte, c);
-                    start = i + 1;
-                }
-                mask ^= 1 << cur;
-                if mask == 0 {
-                    break;
-                }
-                cur = mask.trailing_zeros() as usize;
-            }
+            let mut dst = reserve_block(result, at - start, M256_VECTOR_SIZE, || {
+                escaped_extra(ptr, mask)
+            });
+            dst = process_mask_bits(ptr, at, dst, &mut start, bytes, mask);
+            commit_block(result, dst);
         }
         ptr = ptr.add(M256_VECTOR_SIZE);
     }
@@ -416,7 +404,7 @@ pub unsafe fn escape_avx2(bytes: &[u8], result: &mut Vec<u8>) {
     // Handle tail
     if ptr < end_ptr {
         let d = M256_VECTOR_SIZE - sub(end_ptr, ptr);
-        let mut mask = ({
+        let mask = ({
             let a = _mm256_loadu_si256(ptr.sub(d) as *const __m256i);
             _mm256_movemask_epi8(_mm256_or_si256(
                 _mm256_or_si256(_mm256_cmpeq_epi8(a, v_b), _mm256_cmpeq_epi8(a, v_c)),
@@ -425,26 +413,14 @@ pub unsafe fn escape_avx2(bytes: &[u8], result: &mut Vec<u8>) {
         } as u32)
             .wrapping_shr(d as u32);
 
+        let mask = mask as u64;
         if mask != 0 {
             let at = sub(ptr, start_ptr);
-            let mut cur = mask.trailing_zeros() as usize;
-            loop {
-                let c = *ptr.add(cur);
-                let escape_byte = ESCAPE[c as usize];
-                if escape_byte != 0 {
-                    let i = at + cur;
-                    if start < i {
-                        result.extend_from_slice(&bytes[start..i]);
-                    }
-                    write_escape(result, escape_byte, c);
-                    start = i + 1;
-                }
-                mask ^= 1 << cur;
-                if mask == 0 {
-                    break;
-                }
-                cur = mask.trailing_zeros() as usize;
-            }
+            let mut dst = reserve_block(result, at - start, sub(end_ptr, ptr), || {
+                escaped_extra(ptr, mask)
+            });
+            dst = process_mask_bits(ptr, at, dst, &mut start, bytes, mask);
+            commit_block(result, dst);
         }
     }
 
@@ -475,7 +451,7 @@ pub unsafe fn escape_sse2(bytes: &[u8], result: &mut Vec<u8>) {
     let misalignment = start_ptr as usize & M128_VECTOR_ALIGN;
     if misalignment != 0 {
         let align = M128_VECTOR_SIZE - misalignment;
-        let mut mask = {
+        let mask = {
             let a = _mm_loadu_si128(ptr as *const __m128i);
             _mm_movemask_epi8(_mm_or_si128(
                 _mm_or_si128(_mm_cmpeq_epi8(a, v_b), _mm_cmpeq_epi8(a, v_c)),
@@ -483,26 +459,12 @@ pub unsafe fn escape_sse2(bytes: &[u8], result: &mut Vec<u8>) {
             ))
         };
 
+        let mask = (mask as u32 as u64) & ((1 << align) - 1);
         if mask != 0 {
             let at = sub(ptr, start_ptr);
-            let mut cur = mask.trailing_zeros() as usize;
-            while cur < align {
-                let c = *ptr.add(cur);
-                let escape_byte = ESCAPE[c as usize];
-                if escape_byte != 0 {
-                    let i = at + cur;
-                    if start < i {
-                        result.extend_from_slice(&bytes[start..i]);
-                    }
-                    write_escape(result, escape_byte, c);
-                    start = i + 1;
-                }
-                mask ^= 1 << cur;
-                if mask == 0 {
-                    break;
-                }
-                cur = mask.trailing_zeros() as usize;
-            }
+            let mut dst = reserve_block(result, at - start, align, || escaped_extra(ptr, mask));
+            dst = process_mask_bits(ptr, at, dst, &mut start, bytes, mask);
+            commit_block(result, dst);
         }
         ptr = ptr.add(align);
     }
@@ -510,7 +472,7 @@ pub unsafe fn escape_sse2(bytes: &[u8], result: &mut Vec<u8>) {
     // Main loop
     while ptr <= end_ptr.sub(M128_VECTOR_SIZE) {
         debug_assert_eq!(0, (ptr as usize) % M128_VECTOR_SIZE);
-        let mut mask = {
+        let mask = {
             let a = _mm_load_si128(ptr as *const __m128i);
             _mm_movemask_epi8(_mm_or_si128(
                 _mm_or_si128(_mm_cmpeq_epi8(a, v_b), _mm_cmpeq_epi8(a, v_c)),
@@ -518,26 +480,14 @@ pub unsafe fn escape_sse2(bytes: &[u8], result: &mut Vec<u8>) {
             ))
         };
 
+        let mask = mask as u32 as u64;
         if mask != 0 {
             let at = sub(ptr, start_ptr);
-            let mut cur = mask.trailing_zeros() as usize;
-            loop {
-                let c = *ptr.add(cur);
-                let escape_byte = ESCAPE[c as usize];
-                if escape_byte != 0 {
-                    let i = at + cur;
-                    if start < i {
-                        result.extend_from_slice(&bytes[start..i]);
-                    }
-                    write_escape(result, escape_byte, c);
-                    start = i + 1;
-                }
-                mask ^= 1 << cur;
-                if mask == 0 {
-                    break;
-                }
-                cur = mask.trailing_zeros() as usize;
-            }
+            let mut dst = reserve_block(result, at - start, M128_VECTOR_SIZE, || {
+                escaped_extra(ptr, mask)
+            });
+            dst = process_mask_bits(ptr, at, dst, &mut start, bytes, mask);
+            commit_block(result, dst);
         }
         ptr = ptr.add(M128_VECTOR_SIZE);
     }
@@ -545,7 +495,7 @@ pub unsafe fn escape_sse2(bytes: &[u8], result: &mut Vec<u8>) {
     // Handle tail
     if ptr < end_ptr {
         let d = M128_VECTOR_SIZE - sub(end_ptr, ptr);
-        let mut mask = ({
+        let mask = ({
             let a = _mm_loadu_si128(ptr.sub(d) as *const __m128i);
             _mm_movemask_epi8(_mm_or_si128(
                 _mm_or_si128(_mm_cmpeq_epi8(a, v_b), _mm_cmpeq_epi8(a, v_c)),
@@ -554,26 +504,184 @@ pub unsafe fn escape_sse2(bytes: &[u8], result: &mut Vec<u8>) {
         } as u16)
             .wrapping_shr(d as u32);
 
+        let mask = mask as u64;
         if mask != 0 {
             let at = sub(ptr, start_ptr);
-            let mut cur = mask.trailing_zeros() as usize;
-            loop {
-                let c = *ptr.add(cur);
-                let escape_byte = ESCAPE[c as usize];
-                if escape_byte != 0 {
-                    let i = at + cur;
-                    if start < i {
-                        result.extend_from_slice(&bytes[start..i]);
-                    }
-                    write_escape(result, escape_byte, c);
-                    start = i + 1;
-                }
-                mask ^= 1 << cur;
-                if mask == 0 {
-                    break;
+            let mut dst = reserve_block(result, at - start, sub(end_ptr, ptr), || {
+                escaped_extra(ptr, mask)
+            });
+            dst = process_mask_bits(ptr, at, dst, &mut start, bytes, mask);
+            commit_block(result, dst);
+        }
+    }
+
+    // Copy any remaining bytes
+    if start < len {
+        result.extend_from_slice(&bytes[start..]);
+    }
+}
+
+/// AVX-512 kernel for Ice Lake and later, selected when `avx512vbmi2` is available.
+///
+/// Scans like [`escape_avx512`], but 64-byte blocks with at least [`DENSE_EXPAND`] escapes are
+/// emitted as a whole instead of bit by bit: the escape letters are looked up with `vpermb`, and
+/// `vpexpandb` spreads every 32 input bytes over up to 64 output bytes, filling the gaps with
+/// backslashes. Sparser blocks, and blocks that contain a `\u00XX` escape, keep using the scalar
+/// [`process_mask_bits`] path.
+#[target_feature(
+    enable = "avx512f",
+    enable = "avx512bw",
+    enable = "avx512vbmi",
+    enable = "avx512vbmi2",
+    enable = "bmi2",
+    enable = "popcnt"
+)]
+#[inline]
+pub unsafe fn escape_avx512_vbmi2(bytes: &[u8], result: &mut Vec<u8>) {
+    let len = bytes.len();
+
+    let start_ptr = bytes.as_ptr();
+    let end_ptr = bytes[len..].as_ptr();
+    let mut ptr = start_ptr;
+    let mut start = 0;
+
+    let v_b = _mm512_set1_epi8(B);
+    let v_c = _mm512_set1_epi8(C);
+    let v_ctrl_limit = _mm512_set1_epi8(0x20);
+
+    // Handle alignment - skip if already aligned
+    const M512_VECTOR_ALIGN: usize = M512_VECTOR_SIZE - 1;
+    let misalignment = start_ptr as usize & M512_VECTOR_ALIGN;
+    if misalignment != 0 {
+        let align = M512_VECTOR_SIZE - misalignment;
+        let a = _mm512_loadu_si512(ptr as *const __m512i);
+
+        let quote_mask = _mm512_cmpeq_epi8_mask(a, v_b);
+        let slash_mask = _mm512_cmpeq_epi8_mask(a, v_c);
+        let ctrl_mask = _mm512_cmplt_epu8_mask(a, v_ctrl_limit);
+
+        let mask = (quote_mask | slash_mask | ctrl_mask) & ((1u64 << align) - 1);
+
+        if mask != 0 {
+            let at = sub(ptr, start_ptr);
+            let mut dst = reserve_block(result, at - start, align, || escaped_extra(ptr, mask));
+            dst = process_mask_bits(ptr, at, dst, &mut start, bytes, mask);
+            commit_block(result, dst);
+        }
+        ptr = ptr.add(align);
+    }
+
+    // Main loop processing 256 bytes at a time
+    while ptr <= end_ptr.sub(LOOP_SIZE_AVX512) {
+        debug_assert_eq!(0, (ptr as usize) % M512_VECTOR_SIZE);
+
+        // Prefetch next iteration's data
+        if ptr.add(LOOP_SIZE_AVX512 + PREFETCH_DISTANCE_AVX512) < end_ptr {
+            _mm_prefetch(
+                ptr.add(LOOP_SIZE_AVX512 + PREFETCH_DISTANCE_AVX512) as *const i8,
+                _MM_HINT_T0,
+            );
+        }
+
+        // Load all 4 vectors at once for better pipelining
+        let a = [
+            _mm512_load_si512(ptr as *const __m512i),
+            _mm512_load_si512(ptr.add(M512_VECTOR_SIZE) as *const __m512i),
+            _mm512_load_si512(ptr.add(M512_VECTOR_SIZE * 2) as *const __m512i),
+            _mm512_load_si512(ptr.add(M512_VECTOR_SIZE * 3) as *const __m512i),
+        ];
+        let masks = a.map(|a| {
+            _mm512_cmpeq_epi8_mask(a, v_b)
+                | _mm512_cmpeq_epi8_mask(a, v_c)
+                | _mm512_cmplt_epu8_mask(a, v_ctrl_limit)
+        });
+
+        // Fast path: check if any escaping needed
+        let any_escape = masks[0] | masks[1] | masks[2] | masks[3];
+
+        if any_escape == 0 {
+            // No escapes needed, copy whole chunk along with any pending bytes
+            let end = sub(ptr, start_ptr) + LOOP_SIZE_AVX512;
+            result.extend_from_slice(&bytes[start..end]);
+            start = end;
+        } else if masks.iter().any(|mask| mask.count_ones() >= DENSE_EXPAND) {
+            for (i, (a, mask)) in a.into_iter().zip(masks).enumerate() {
+                if mask != 0 {
+                    escape_block_vbmi2(
+                        ptr.add(i * M512_VECTOR_SIZE),
+                        a,
+                        mask,
+                        result,
+                        &mut start,
+                        bytes,
+                    );
                 }
-                cur = mask.trailing_zeros() as usize;
             }
+        } else {
+            // Process each 64-byte chunk that has escapes
+            let mut dst = reserve_block(
+                result,
+                sub(ptr, start_ptr) - start,
+                LOOP_SIZE_AVX512,
+                || {
+                    escaped_extra(ptr, masks[0])
+                        + escaped_extra(ptr.add(M512_VECTOR_SIZE), masks[1])
+                        + escaped_extra(ptr.add(M512_VECTOR_SIZE * 2), masks[2])
+                        + escaped_extra(ptr.add(M512_VECTOR_SIZE * 3), masks[3])
+                },
+            );
+            for (i, mask) in masks.into_iter().enumerate() {
+                dst = process_mask_avx512(
+                    ptr,
+                    start_ptr,
+                    dst,
+                    &mut start,
+                    bytes,
+                    mask,
+                    i * M512_VECTOR_SIZE,
+                );
+            }
+            commit_block(result, dst);
+        }
+
+        ptr = ptr.add(LOOP_SIZE_AVX512);
+    }
+
+    // Process remaining aligned chunks
+    while ptr <= end_ptr.sub(M512_VECTOR_SIZE) {
+        debug_assert_eq!(0, (ptr as usize) % M512_VECTOR_SIZE);
+        let a = _mm512_load_si512(ptr as *const __m512i);
+
+        let quote_mask = _mm512_cmpeq_epi8_mask(a, v_b);
+        let slash_mask = _mm512_cmpeq_epi8_mask(a, v_c);
+        let ctrl_mask = _mm512_cmplt_epu8_mask(a, v_ctrl_limit);
+
+        let mask = quote_mask | slash_mask | ctrl_mask;
+
+        if mask != 0 {
+            escape_block_vbmi2(ptr, a, mask, result, &mut start, bytes);
+        }
+        ptr = ptr.add(M512_VECTOR_SIZE);
+    }
+
+    // Handle tail
+    if ptr < end_ptr {
+        let d = M512_VECTOR_SIZE - sub(end_ptr, ptr);
+        let a = _mm512_loadu_si512(ptr.sub(d) as *const __m512i);
+
+        let quote_mask = _mm512_cmpeq_epi8_mask(a, v_b);
+        let slash_mask = _mm512_cmpeq_epi8_mask(a, v_c);
+        let ctrl_mask = _mm512_cmplt_epu8_mask(a, v_ctrl_limit);
+
+        let mask = (quote_mask | slash_mask | ctrl_mask).wrapping_shr(d as u32);
+
+        if mask != 0 {
+            let at = sub(ptr, start_ptr);
+            let mut dst = reserve_block(result, at - start, sub(end_ptr, ptr), || {
+                escaped_extra(ptr, mask)
+            });
+            dst = process_mask_bits(ptr, at, dst, &mut start, bytes, mask);
+            commit_block(result, dst);
         }
     }
 
@@ -583,95 +691,595 @@ pub unsafe fn escape_sse2(bytes: &[u8], result: &mut Vec<u8>) {
     }
 }
 
-#[inline(always)]
-unsafe fn process_mask_avx(
+/// Escape the 64 bytes `a` loaded from `ptr`, whose escapes are set in `mask`.
+///
+/// Takes the `vpexpandb` path when the block is dense and has only short escapes, otherwise
+/// handles the escapes one by one.
+#[target_feature(
+    enable = "avx512f",
+    enable = "avx512bw",
+    enable = "avx512vbmi",
+    enable = "avx512vbmi2",
+    enable = "bmi2",
+    enable = "popcnt"
+)]
+#[inline]
+unsafe fn escape_block_vbmi2(
     ptr: *const u8,
-    start_ptr: *const u8,
+    a: __m512i,
+    mask: u64,
     result: &mut Vec<u8>,
     start: &mut usize,
     bytes: &[u8],
-    mask: i32,
-    offset: usize,
 ) {
-    if mask == 0 {
-        return;
+    let at = sub(ptr, bytes.as_ptr());
+
+    if mask.count_ones() >= DENSE_EXPAND {
+        let ctrl_mask = _mm512_cmplt_epu8_mask(a, _mm512_set1_epi8(0x20));
+        // `vpermb` only looks at the low 6 bits, the first 64 entries cover every control
+        // character. `"` and `\` escape as themselves, control characters go through the table
+        let letters =
+            _mm512_permutexvar_epi8(a, _mm512_loadu_si512(ESCAPE.as_ptr() as *const __m512i));
+        let unicode_mask =
+            _mm512_mask_cmpeq_epi8_mask(ctrl_mask, letters, _mm512_set1_epi8(UU as i8));
+
+        if unicode_mask == 0 {
+            if *start < at {
+                result.extend_from_slice(&bytes[*start..at]);
+            }
+            let escaped = _mm512_mask_blend_epi8(ctrl_mask, a, letters);
+            // each half writes at most 64 bytes, the second store may start right after the first
+            result.reserve(2 * M512_VECTOR_SIZE);
+            let dst = result.as_mut_ptr().add(result.len());
+            let written = expand_half_vbmi2(dst, escaped, mask as u32);
+            let written = written
+                + expand_half_vbmi2(
+                    dst.add(written),
+                    _mm512_shuffle_i64x2::<0b11_10_11_10>(escaped, escaped),
+                    (mask >> 32) as u32,
+                );
+            result.set_len(result.len() + written);
+            *start = at + M512_VECTOR_SIZE;
+            return;
+        }
     }
 
-    let ptr = ptr.add(offset);
-    let at = sub(ptr, start_ptr);
+    let mut dst = reserve_block(result, at - *start, M512_VECTOR_SIZE, || {
+        escaped_extra(ptr, mask)
+    });
+    dst = process_mask_bits(ptr, at, dst, start, bytes, mask);
+    commit_block(result, dst);
+}
 
-    // Process mask bits using bit manipulation
-    let mut remaining = mask as u32;
-    while remaining != 0 {
-        let cur = remaining.trailing_zeros() as usize;
-        let c = *ptr.add(cur);
-        let escape_byte = ESCAPE[c as usize];
-        debug_assert!(escape_byte != 0);
+/// Write the low 32 bytes of `escaped` to `dst`, with a backslash in front of every byte set in
+/// `mask`. Returns the number of bytes written, always stores 64 bytes.
+#[target_feature(
+    enable = "avx512f",
+    enable = "avx512bw",
+    enable = "avx512vbmi2",
+    enable = "bmi2"
+)]
+#[inline]
+unsafe fn expand_half_vbmi2(dst: *mut u8, escaped: __m512i, mask: u32) -> usize {
+    // Spread the mask to one bit pair per input byte: (backslash?, byte). Dropping the unused
+    // backslash bits leaves a bit per output byte, set where a backslash goes.
+    let spread = _pdep_u64(mask as u64, 0x5555_5555_5555_5555);
+    let slashes = _pext_u64(spread, spread | 0xAAAA_AAAA_AAAA_AAAA);
+    let out = _mm512_mask_expand_epi8(_mm512_set1_epi8(C), !slashes, escaped);
+    _mm512_storeu_si512(dst as *mut __m512i, out);
+    M256_VECTOR_SIZE + mask.count_ones() as usize
+}
+
+/// Short input path for AVX-512BW hosts, used below [`LOOP_SIZE_AVX512`].
+///
+/// Every 64-byte chunk is read with a single masked load, so the tail never needs a scalar drain
+/// and masked-off lanes can't fault.
+#[target_feature(enable = "avx512f", enable = "avx512bw")]
+#[inline]
+pub unsafe fn escape_short_avx512(bytes: &[u8], result: &mut Vec<u8>) {
+    let len = bytes.len();
 
-        let i = at + cur;
-        // Copy unescaped portion if needed
-        if *start < i {
-            result.extend_from_slice(&bytes[*start..i]);
+    let start_ptr = bytes.as_ptr();
+    let mut offset = 0;
+    let mut start = 0;
+
+    let v_b = _mm512_set1_epi8(B);
+    let v_c = _mm512_set1_epi8(C);
+    let v_ctrl_limit = _mm512_set1_epi8(0x20);
+
+    while offset < len {
+        let remaining = len - offset;
+        let k = if remaining >= M512_VECTOR_SIZE {
+            u64::MAX
+        } else {
+            (1u64 << remaining) - 1
+        };
+        let a = _mm512_maskz_loadu_epi8(k, start_ptr.add(offset) as *const i8);
+
+        // Masked-off lanes are zero and would classify as control characters
+        let quote_mask = _mm512_mask_cmpeq_epi8_mask(k, a, v_b);
+        let slash_mask = _mm512_mask_cmpeq_epi8_mask(k, a, v_c);
+        let ctrl_mask = _mm512_mask_cmplt_epu8_mask(k, a, v_ctrl_limit);
+
+        let mask = quote_mask | slash_mask | ctrl_mask;
+        if mask != 0 {
+            let block = remaining.min(M512_VECTOR_SIZE);
+            let mut dst = reserve_block(result, offset - start, block, || {
+                escaped_extra(start_ptr.add(offset), mask)
+            });
+            dst = process_mask_avx512(start_ptr, start_ptr, dst, &mut start, bytes, mask, offset);
+            commit_block(result, dst);
+        }
+        offset += M512_VECTOR_SIZE;
+    }
+
+    // Copy any remaining bytes
+    if start < len {
+        result.extend_from_slice(&bytes[start..]);
+    }
+}
+
+/// 256-bit AVX-512VL kernel, used instead of the 512-bit kernels when the width is capped with
+/// [`crate::set_max_vector_width`].
+///
+/// Compares go to mask registers like in [`escape_avx512`], but only `ymm` registers are used so
+/// the core keeps its AVX2 frequency licence. The input is read unaligned and the tail with a
+/// masked load, so every length goes through this kernel.
+#[target_feature(enable = "avx512f", enable = "avx512bw", enable = "avx512vl")]
+#[inline]
+pub unsafe fn escape_avx512vl(bytes: &[u8], result: &mut Vec<u8>) {
+    let len = bytes.len();
+
+    let start_ptr = bytes.as_ptr();
+    let mut offset = 0;
+    let mut start = 0;
+
+    let v_b = _mm256_set1_epi8(B);
+    let v_c = _mm256_set1_epi8(C);
+    let v_ctrl_limit = _mm256_set1_epi8(0x20);
+
+    // Main loop processing 128 bytes at a time
+    while offset + LOOP_SIZE_AVX2 <= len {
+        let ptr = start_ptr.add(offset);
+
+        // Prefetch next iteration's data
+        if offset + LOOP_SIZE_AVX2 + PREFETCH_DISTANCE_AVX2 < len {
+            _mm_prefetch(
+                ptr.add(LOOP_SIZE_AVX2 + PREFETCH_DISTANCE_AVX2) as *const i8,
+                _MM_HINT_T0,
+            );
+        }
+
+        let a0 = _mm256_loadu_si256(ptr as *const __m256i);
+        let a1 = _mm256_loadu_si256(ptr.add(M256_VECTOR_SIZE) as *const __m256i);
+        let a2 = _mm256_loadu_si256(ptr.add(M256_VECTOR_SIZE * 2) as *const __m256i);
+        let a3 = _mm256_loadu_si256(ptr.add(M256_VECTOR_SIZE * 3) as *const __m256i);
+
+        let mask_a = _mm256_cmpeq_epi8_mask(a0, v_b)
+            | _mm256_cmpeq_epi8_mask(a0, v_c)
+            | _mm256_cmplt_epu8_mask(a0, v_ctrl_limit);
+        let mask_b = _mm256_cmpeq_epi8_mask(a1, v_b)
+            | _mm256_cmpeq_epi8_mask(a1, v_c)
+            | _mm256_cmplt_epu8_mask(a1, v_ctrl_limit);
+        let mask_c = _mm256_cmpeq_epi8_mask(a2, v_b)
+            | _mm256_cmpeq_epi8_mask(a2, v_c)
+            | _mm256_cmplt_epu8_mask(a2, v_ctrl_limit);
+        let mask_d = _mm256_cmpeq_epi8_mask(a3, v_b)
+            | _mm256_cmpeq_epi8_mask(a3, v_c)
+            | _mm256_cmplt_epu8_mask(a3, v_ctrl_limit);
+
+        // Clean blocks stay pending and are copied along with the next escape
+        if mask_a | mask_b | mask_c | mask_d != 0 {
+            let mut dst = reserve_block(result, offset - start, LOOP_SIZE_AVX2, || {
+                escaped_extra(ptr, mask_a as u64)
+                    + escaped_extra(ptr.add(M256_VECTOR_SIZE), mask_b as u64)
+                    + escaped_extra(ptr.add(M256_VECTOR_SIZE * 2), mask_c as u64)
+                    + escaped_extra(ptr.add(M256_VECTOR_SIZE * 3), mask_d as u64)
+            });
+            dst = process_mask_avx(ptr, start_ptr, dst, &mut start, bytes, mask_a as i32, 0);
+            dst = process_mask_avx(
+                ptr,
+                start_ptr,
+                dst,
+                &mut start,
+                bytes,
+                mask_b as i32,
+                M256_VECTOR_SIZE,
+            );
+            dst = process_mask_avx(
+                ptr,
+                start_ptr,
+                dst,
+                &mut start,
+                bytes,
+                mask_c as i32,
+                M256_VECTOR_SIZE * 2,
+            );
+            dst = process_mask_avx(
+                ptr,
+                start_ptr,
+                dst,
+                &mut start,
+                bytes,
+                mask_d as i32,
+                M256_VECTOR_SIZE * 3,
+            );
+            commit_block(result, dst);
+        }
+
+        offset += LOOP_SIZE_AVX2;
+    }
+
+    // Process the rest 32 bytes at a time, the last load is masked
+    while offset < len {
+        let remaining = len - offset;
+        let k = if remaining >= M256_VECTOR_SIZE {
+            u32::MAX
+        } else {
+            (1u32 << remaining) - 1
+        };
+        let a = _mm256_maskz_loadu_epi8(k, start_ptr.add(offset) as *const i8);
+
+        // Masked-off lanes are zero and would classify as control characters
+        let mask = _mm256_mask_cmpeq_epi8_mask(k, a, v_b)
+            | _mm256_mask_cmpeq_epi8_mask(k, a, v_c)
+            | _mm256_mask_cmplt_epu8_mask(k, a, v_ctrl_limit);
+        if mask != 0 {
+            let block = remaining.min(M256_VECTOR_SIZE);
+            let mut dst = reserve_block(result, offset - start, block, || {
+                escaped_extra(start_ptr.add(offset), mask as u64)
+            });
+            dst = process_mask_avx(
+                start_ptr,
+                start_ptr,
+                dst,
+                &mut start,
+                bytes,
+                mask as i32,
+                offset,
+            );
+            commit_block(result, dst);
+        }
+        offset += M256_VECTOR_SIZE;
+    }
+
+    // Copy any remaining bytes
+    if start < len {
+        result.extend_from_slice(&bytes[start..]);
+    }
+}
+
+/// SSSE3 kernel for hosts without AVX2 (Atom, Silvermont) and for inputs between 16 and 128
+/// bytes on hosts without AVX-512BW.
+///
+/// Bytes are classified with two `pshufb` nibble lookups instead of the signed-compare trick,
+/// blocks are read unaligned so there is no alignment prologue, and the last block overlaps the
+/// previous one instead of falling back to a scalar tail.
+#[target_feature(enable = "ssse3")]
+#[inline]
+pub unsafe fn escape_ssse3(bytes: &[u8], result: &mut Vec<u8>) {
+    let len = bytes.len();
+    debug_assert!(len >= M128_VECTOR_SIZE);
+
+    let start_ptr = bytes.as_ptr();
+    let mut offset = 0;
+    let mut start = 0;
+
+    let (lo_table, hi_table) = nibble_tables_ssse3();
+
+    while offset + M128_VECTOR_SIZE <= len {
+        let ptr = start_ptr.add(offset);
+        let mask = classify_ssse3(ptr, lo_table, hi_table);
+        let mask = mask as u64;
+        if mask != 0 {
+            let mut dst = reserve_block(result, offset - start, M128_VECTOR_SIZE, || {
+                escaped_extra(ptr, mask)
+            });
+            dst = process_mask_groups(ptr, start_ptr, dst, &mut start, bytes, mask, 1);
+            commit_block(result, dst);
         }
-        // Write escape sequence
-        write_escape(result, escape_byte, c);
-        *start = i + 1;
+        offset += M128_VECTOR_SIZE;
+    }
+
+    // Handle tail with an overlapping load ending at the last byte
+    if offset < len {
+        let d = M128_VECTOR_SIZE - (len - offset);
+        let mask = classify_ssse3(start_ptr.add(len - M128_VECTOR_SIZE), lo_table, hi_table) >> d;
+        let mask = mask as u64;
+        if mask != 0 {
+            let mut dst = reserve_block(result, offset - start, len - offset, || {
+                escaped_extra(start_ptr.add(offset), mask)
+            });
+            dst = process_mask_bits(start_ptr.add(offset), offset, dst, &mut start, bytes, mask);
+            commit_block(result, dst);
+        }
+    }
+
+    // Copy any remaining bytes
+    if start < len {
+        result.extend_from_slice(&bytes[start..]);
+    }
+}
+
+/// Nibble tables for [`classify_ssse3`]. A byte needs escaping when the entries for its low and
+/// high nibble share a bit: bit 0 marks `0x00..=0x1F`, bit 1 marks `"` and bit 2 marks `\`.
+#[target_feature(enable = "ssse3")]
+#[inline]
+unsafe fn nibble_tables_ssse3() -> (__m128i, __m128i) {
+    let lo_table = _mm_setr_epi8(1, 1, 1 | 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1 | 4, 1, 1, 1);
+    let hi_table = _mm_setr_epi8(1, 1, 2, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0);
+    (lo_table, hi_table)
+}
+
+/// Load 16 bytes from `ptr` and return the mask of bytes that need escaping.
+#[target_feature(enable = "ssse3")]
+#[inline]
+unsafe fn classify_ssse3(ptr: *const u8, lo_table: __m128i, hi_table: __m128i) -> u32 {
+    let a = _mm_loadu_si128(ptr as *const __m128i);
+    // `pshufb` yields zero for bytes with the top bit set, which never need escaping
+    let lo = _mm_shuffle_epi8(lo_table, a);
+    let hi = _mm_shuffle_epi8(
+        hi_table,
+        _mm_and_si128(_mm_srli_epi16::<4>(a), _mm_set1_epi8(0x0F)),
+    );
+    let clean = _mm_cmpeq_epi8(_mm_and_si128(lo, hi), _mm_setzero_si128());
+    !(_mm_movemask_epi8(clean) as u32) & 0xFFFF
+}
 
-        // Clear the lowest set bit
-        remaining &= remaining - 1;
+#[inline(always)]
+unsafe fn process_mask_avx(
+    ptr: *const u8,
+    start_ptr: *const u8,
+    dst: *mut u8,
+    start: &mut usize,
+    bytes: &[u8],
+    mask: i32,
+    offset: usize,
+) -> *mut u8 {
+    if mask == 0 {
+        return dst;
     }
+
+    process_mask_groups(
+        ptr.add(offset),
+        start_ptr,
+        dst,
+        start,
+        bytes,
+        mask as u32 as u64,
+        M256_VECTOR_SIZE / M128_VECTOR_SIZE,
+    )
 }
 
 #[inline(always)]
 unsafe fn process_mask_avx512(
     ptr: *const u8,
     start_ptr: *const u8,
-    result: &mut Vec<u8>,
+    dst: *mut u8,
     start: &mut usize,
     bytes: &[u8],
     mask: u64,
     offset: usize,
-) {
+) -> *mut u8 {
     if mask == 0 {
-        return;
+        return dst;
     }
 
-    let ptr = ptr.add(offset);
+    process_mask_groups(
+        ptr.add(offset),
+        start_ptr,
+        dst,
+        start,
+        bytes,
+        mask,
+        M512_VECTOR_SIZE / M128_VECTOR_SIZE,
+    )
+}
+
+/// Handle the escapes of a block in 16-byte groups. Dense groups are expanded at once with
+/// [`escape_block_ssse3`], so callers must imply SSSE3.
+#[inline(always)]
+unsafe fn process_mask_groups(
+    ptr: *const u8,
+    start_ptr: *const u8,
+    mut dst: *mut u8,
+    start: &mut usize,
+    bytes: &[u8],
+    mask: u64,
+    groups: usize,
+) -> *mut u8 {
     let at = sub(ptr, start_ptr);
 
-    // Process mask bits using bit manipulation
-    let mut remaining = mask;
-    while remaining != 0 {
-        let cur = remaining.trailing_zeros() as usize;
-        let c = *ptr.add(cur);
-        let escape_byte = ESCAPE[c as usize];
-        debug_assert!(escape_byte != 0);
+    for g in 0..groups {
+        let group = (mask >> (g * M128_VECTOR_SIZE)) & 0xFFFF;
+        if group == 0 {
+            continue;
+        }
+        let group_ptr = ptr.add(g * M128_VECTOR_SIZE);
+        let group_at = at + g * M128_VECTOR_SIZE;
+        // the block emitter reads a full group, so it can't be used past the end of the input
+        if is_dense(group) && group_at + M128_VECTOR_SIZE <= bytes.len() {
+            // Copy unescaped portion if needed
+            if *start < group_at {
+                dst = copy_pending(dst, bytes, *start, group_at);
+            }
+            dst = dst.add(escape_block_ssse3(group_ptr, dst));
+            *start = group_at + M128_VECTOR_SIZE;
+        } else {
+            dst = process_mask_bits(group_ptr, group_at, dst, start, bytes, group);
+        }
+    }
+    dst
+}
+
+/// Whether `mask` has at least [`DENSE_ESCAPES`] bits set. Clearing the lowest bits is cheaper
+/// than the `popcnt` fallback, the kernels calling this don't enable that target feature.
+#[inline(always)]
+fn is_dense(mask: u64) -> bool {
+    let mut m = mask;
+    for _ in 1..DENSE_ESCAPES {
+        m &= m.wrapping_sub(1);
+    }
+    m != 0
+}
+
+/// Escape 16 bytes from `src` into `dst` without a per-byte branch.
+///
+/// Every input byte becomes an 8-byte slot laid out like an `ESCAPE_SEQ` entry: the escape
+/// letters and `\u00XX` hex digits come from `pshufb` lookups, the slots are built by
+/// interleaving, and each one is written with a fixed-width store while the cursor advances by
+/// its real length.
+/// `dst` must have room for `16 * 8` bytes. Returns the number of bytes written.
+#[target_feature(enable = "ssse3")]
+#[inline]
+unsafe fn escape_block_ssse3(src: *const u8, dst: *mut u8) -> usize {
+    #[inline(always)]
+    unsafe fn select(mask: __m128i, a: __m128i, b: __m128i) -> __m128i {
+        _mm_or_si128(_mm_and_si128(mask, a), _mm_andnot_si128(mask, b))
+    }
 
-        let i = at + cur;
-        // Copy unescaped portion if needed
-        if *start < i {
-            result.extend_from_slice(&bytes[*start..i]);
+    let a = _mm_loadu_si128(src as *const __m128i);
+
+    // unsigned `a < 0x20`
+    let ctrl = _mm_cmpeq_epi8(_mm_min_epu8(a, _mm_set1_epi8(0x1F)), a);
+    let escaped = _mm_or_si128(
+        ctrl,
+        _mm_or_si128(
+            _mm_cmpeq_epi8(a, _mm_set1_epi8(B)),
+            _mm_cmpeq_epi8(a, _mm_set1_epi8(C)),
+        ),
+    );
+
+    // Short escapes only exist for 0x08..=0x0D, the rest of the control range is `\u00XX`
+    let short = _mm_shuffle_epi8(
+        _mm_setr_epi8(
+            UU as i8, UU as i8, UU as i8, UU as i8, UU as i8, UU as i8, UU as i8, UU as i8,
+            b'b' as i8, b't' as i8, b'n' as i8, UU as i8, b'f' as i8, b'r' as i8, UU as i8,
+            UU as i8,
+        ),
+        a,
+    );
+    let v_uu = _mm_set1_epi8(UU as i8);
+    let above_0f = _mm_cmpeq_epi8(_mm_and_si128(a, _mm_set1_epi8(0x10)), _mm_set1_epi8(0x10));
+    // `"` and `\` escape as themselves
+    let letters = select(ctrl, select(above_0f, v_uu, short), a);
+    let unicode = _mm_and_si128(ctrl, _mm_cmpeq_epi8(letters, v_uu));
+
+    let first = select(escaped, _mm_set1_epi8(C), a);
+    let zeros = _mm_set1_epi8(b'0' as i8);
+    let hex_hi = _mm_or_si128(
+        zeros,
+        _mm_and_si128(_mm_srli_epi16::<4>(a), _mm_set1_epi8(0x01)),
+    );
+    let hex_lo = _mm_shuffle_epi8(
+        _mm_setr_epi8(
+            b'0' as i8, b'1' as i8, b'2' as i8, b'3' as i8, b'4' as i8, b'5' as i8, b'6' as i8,
+            b'7' as i8, b'8' as i8, b'9' as i8, b'a' as i8, b'b' as i8, b'c' as i8, b'd' as i8,
+            b'e' as i8, b'f' as i8,
+        ),
+        _mm_and_si128(a, _mm_set1_epi8(0x0F)),
+    );
+
+    // 1 for plain bytes, 2 for short escapes, 6 for `\u00XX`
+    let lens = _mm_add_epi8(
+        _mm_sub_epi8(_mm_set1_epi8(1), escaped),
+        _mm_and_si128(unicode, _mm_set1_epi8(4)),
+    );
+    let mut lengths = [0u8; M128_VECTOR_SIZE];
+    _mm_storeu_si128(lengths.as_mut_ptr() as *mut __m128i, lens);
+
+    // Interleave into 8-byte slots: [first, letter, '0', '0', hex_hi, hex_lo, 0, 0]
+    let zero = _mm_setzero_si128();
+    let pairs_lo = _mm_unpacklo_epi8(first, letters);
+    let pairs_hi = _mm_unpackhi_epi8(first, letters);
+    let hex_lo_pairs = _mm_unpacklo_epi8(hex_hi, hex_lo);
+    let hex_hi_pairs = _mm_unpackhi_epi8(hex_hi, hex_lo);
+    let heads = [
+        _mm_unpacklo_epi16(pairs_lo, zeros),
+        _mm_unpackhi_epi16(pairs_lo, zeros),
+        _mm_unpacklo_epi16(pairs_hi, zeros),
+        _mm_unpackhi_epi16(pairs_hi, zeros),
+    ];
+    let tails = [
+        _mm_unpacklo_epi16(hex_lo_pairs, zero),
+        _mm_unpackhi_epi16(hex_lo_pairs, zero),
+        _mm_unpacklo_epi16(hex_hi_pairs, zero),
+        _mm_unpackhi_epi16(hex_hi_pairs, zero),
+    ];
+
+    let mut out = dst;
+    for q in 0..4 {
+        let slots = [
+            _mm_unpacklo_epi32(heads[q], tails[q]),
+            _mm_unpackhi_epi32(heads[q], tails[q]),
+        ];
+        for (s, slot) in slots.into_iter().enumerate() {
+            let j = q * 4 + s * 2;
+            _mm_storel_epi64(out as *mut __m128i, slot);
+            out = out.add(lengths[j] as usize);
+            _mm_storel_epi64(out as *mut __m128i, _mm_unpackhi_epi64(slot, slot));
+            out = out.add(lengths[j + 1] as usize);
         }
-        // Write escape sequence
-        write_escape(result, escape_byte, c);
-        *start = i + 1;
+    }
+    sub(out, dst)
+}
+
+/// Index of the first byte at or after `i` that is in `class`, or `bytes.len()`.
+#[target_feature(enable = "sse2")]
+#[inline]
+pub unsafe fn find_in_class_sse2<const N: usize>(
+    bytes: &[u8],
+    mut i: usize,
+    class: ByteClass<N>,
+) -> usize {
+    let v_translation = _mm_set1_epi8(translation(class.below));
+    let v_threshold = _mm_set1_epi8(threshold(class.below));
+    let mut v_bytes = [_mm_setzero_si128(); N];
+    for (v, b) in v_bytes.iter_mut().zip(class.bytes) {
+        *v = _mm_set1_epi8(b as i8);
+    }
 
-        // Clear the lowest set bit
-        remaining &= remaining - 1;
+    while i + M128_VECTOR_SIZE <= bytes.len() {
+        let a = _mm_loadu_si128(bytes.as_ptr().add(i) as *const __m128i);
+        let mut matches = _mm_cmpgt_epi8(_mm_add_epi8(a, v_translation), v_threshold);
+        for v in v_bytes {
+            matches = _mm_or_si128(matches, _mm_cmpeq_epi8(a, v));
+        }
+        let mask = _mm_movemask_epi8(matches);
+        if mask != 0 {
+            return i + mask.trailing_zeros() as usize;
+        }
+        i += M128_VECTOR_SIZE;
     }
+    next_in_class(bytes, i, class)
 }
 
-#[inline(always)]
-fn write_escape(result: &mut Vec<u8>, escape_byte: u8, c: u8) {
-    result.push(b'\\');
-    if escape_byte == UU {
-        // Unicode escape for control characters
-        result.extend_from_slice(b"u00");
-        let hex_digits = &HEX_BYTES[c as usize];
-        result.push(hex_digits.0);
-        result.push(hex_digits.1);
-    } else {
-        // Simple escape
-        result.push(escape_byte);
+/// Index of the first byte at or after `i` that is in `class`, or `bytes.len()`.
+#[target_feature(enable = "avx2")]
+#[inline]
+pub unsafe fn find_in_class_avx2<const N: usize>(
+    bytes: &[u8],
+    mut i: usize,
+    class: ByteClass<N>,
+) -> usize {
+    let v_translation = _mm256_set1_epi8(translation(class.below));
+    let v_threshold = _mm256_set1_epi8(threshold(class.below));
+    let mut v_bytes = [_mm256_setzero_si256(); N];
+    for (v, b) in v_bytes.iter_mut().zip(class.bytes) {
+        *v = _mm256_set1_epi8(b as i8);
+    }
+
+    while i + M256_VECTOR_SIZE <= bytes.len() {
+        let a = _mm256_loadu_si256(bytes.as_ptr().add(i) as *const __m256i);
+        let mut matches = _mm256_cmpgt_epi8(_mm256_add_epi8(a, v_translation), v_threshold);
+        for v in v_bytes {
+            matches = _mm256_or_si256(matches, _mm256_cmpeq_epi8(a, v));
+        }
+        let mask = _mm256_movemask_epi8(matches);
+        if mask != 0 {
+            return i + mask.trailing_zeros() as usize;
+        }
+        i += M256_VECTOR_SIZE;
     }
+    find_in_class_sse2(bytes, i, class)
 }